@@ -227,6 +227,59 @@ pub fn execute(info: &EarlyInfo) -> Result<()> {
             .display_order(1)
             .help("Don't write new versions")
         )
+        .arg(
+          Arg::with_name("pre")
+            .long("pre")
+            .takes_value(true)
+            .value_name("channel")
+            .conflicts_with_all(&["resume", "abort"])
+            .display_order(1)
+            .help("Release as a pre-release")
+        )
+        .display_order(1)
+    )
+    .subcommand(
+      SubCommand::with_name("promote")
+        .setting(AppSettings::UnifiedHelpMessage)
+        .about("Promote a pre-release to a final release")
+        .arg(
+          Arg::with_name("name")
+            .short("n")
+            .long("name")
+            .takes_value(true)
+            .value_name("name")
+            .display_order(1)
+            .help("The name to promote")
+        )
+        .arg(
+          Arg::with_name("id")
+            .short("i")
+            .long("id")
+            .takes_value(true)
+            .value_name("id")
+            .display_order(1)
+            .help("The id to promote")
+        )
+        .group(ArgGroup::with_name("ident").args(&["id", "name"]).required(id_required))
+        .arg(
+          Arg::with_name("pause")
+            .short("p")
+            .long("pause")
+            .takes_value(true)
+            .value_name("stage")
+            .possible_values(&["commit"])
+            .display_order(1)
+            .help("Pause the promotion")
+        )
+        .arg(
+          Arg::with_name("dry")
+            .short("d")
+            .long("dry-run")
+            .takes_value(false)
+            .conflicts_with("pause")
+            .display_order(1)
+            .help("Don't write new versions")
+        )
         .display_order(1)
     )
     .subcommand(
@@ -248,6 +301,13 @@ pub fn execute(info: &EarlyInfo) -> Result<()> {
             .display_order(1)
             .help("Max descent to search")
         )
+        .arg(
+          Arg::with_name("merge")
+            .long("merge")
+            .takes_value(false)
+            .display_order(1)
+            .help("Add new projects to an existing config")
+        )
         .display_order(1)
     )
     .subcommand(
@@ -385,8 +445,15 @@ fn parse_matches(m: ArgMatches) -> Result<()> {
     ("plan", Some(_)) => plan(pref_vcs, ignore_current)?,
     ("release", Some(m)) if m.is_present("abort") => abort()?,
     ("release", Some(m)) if m.is_present("resume") => resume(pref_vcs)?,
-    ("release", Some(m)) => release(pref_vcs, m.is_present("all"), m.is_present("dry"), m.is_present("pause"))?,
-    ("init", Some(m)) => init(m.value_of("maxdepth").map(|d| d.parse().unwrap()).unwrap_or(5))?,
+    ("release", Some(m)) => {
+      release(pref_vcs, m.is_present("all"), m.is_present("dry"), m.is_present("pause"), m.value_of("pre"))?
+    }
+    ("promote", Some(m)) => {
+      promote(pref_vcs, m.value_of("id"), m.value_of("name"), m.is_present("dry"), m.is_present("pause"))?
+    }
+    ("init", Some(m)) => {
+      init(m.value_of("maxdepth").map(|d| d.parse().unwrap()).unwrap_or(5), m.is_present("merge"))?
+    }
     ("info", Some(m)) => {
       let names = m.values_of("name").map(|v| v.collect::<Vec<_>>()).unwrap_or_default();
       let labels = m.values_of("label").map(|v| v.collect::<Vec<_>>()).unwrap_or_default();
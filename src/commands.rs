@@ -5,12 +5,15 @@ use crate::errors::{Result, ResultExt};
 use crate::git::Repo;
 use crate::mono::Mono;
 use crate::output::{Output, ProjLine};
+use crate::scheme::VersionScheme;
 use crate::state::{CommitState, StateRead};
 use crate::vcs::{VcsLevel, VcsRange, VcsState};
+use crate::version::validate_channel;
 use error_chain::bail;
 use std::collections::HashMap;
 use std::fs::{remove_file, File};
 use std::io::BufReader;
+use std::iter::once;
 use std::path::{Path, PathBuf};
 
 pub fn early_info() -> Result<EarlyInfo> {
@@ -257,11 +260,16 @@ impl InfoShow {
   }
 }
 
-pub fn release(pref_vcs: Option<VcsRange>, all: bool, dry: bool, pause: bool) -> Result<()> {
+pub fn release(pref_vcs: Option<VcsRange>, all: bool, dry: bool, pause: bool, pre: Option<&str>) -> Result<()> {
+  if let Some(pre) = pre {
+    validate_channel(pre)?;
+  }
+
   let mut mono = build(pref_vcs, VcsLevel::None, VcsLevel::Smart, VcsLevel::Local, VcsLevel::Smart)?;
   let output = Output::new();
   let mut output = output.release();
   let plan = mono.build_plan()?;
+  let branch_pre = mono.branch_prerelease().map(|c| c.to_string());
 
   if let Err((should, is)) = mono.check_branch() {
     bail!("Branch name \"{}\"\" doesn't match \"{}\".", is, should);
@@ -288,8 +296,22 @@ pub fn release(pref_vcs: Option<VcsRange>, all: bool, dry: bool, pause: bool) ->
       output.write_no_change(all, name.clone(), prev_vers.clone(), curt_vers.clone())?;
       curt_vers
     } else if let Some(prev_vers) = prev_vers {
-      let target = size.apply(&prev_vers)?;
-      if Size::less_than(&curt_vers, &target)? {
+      let scheme = proj.scheme();
+      let size = proj.adjust_size(*size, &prev_vers)?;
+      let target = if let Some(release_as) = plan.release_as(id) {
+        check_release_as(scheme, &name, &curt_vers, release_as)?;
+        release_as.to_string()
+      } else {
+        match pre.or_else(|| proj.prerelease()).or(branch_pre.as_deref()) {
+          Some(channel) => {
+            let tagged = curt_config.old_tags().all_versions(id).iter().map(|v| v.as_str());
+            let existing: Vec<_> = tagged.chain(once(curt_vers.as_str())).chain(once(prev_vers.as_str())).collect();
+            scheme.apply_pre(size, &prev_vers, channel, &existing)?
+          }
+          None => scheme.apply(size, &prev_vers)?
+        }
+      };
+      if scheme.less_than(&curt_vers, &target)? {
         proj.verify_restrictions(&target)?;
         mono.set_by_id(id, &target)?;
         output.write_changed(name.clone(), prev_vers.clone(), curt_vers.clone(), target.clone())?;
@@ -299,6 +321,13 @@ pub fn release(pref_vcs: Option<VcsRange>, all: bool, dry: bool, pause: bool) ->
         output.write_forward(all, name.clone(), prev_vers.clone(), curt_vers.clone(), target.clone())?;
       }
       target
+    } else if let Some(release_as) = plan.release_as(id) {
+      // A project without a previous version has nothing to increment, but can still be released as a given version.
+      check_release_as(proj.scheme(), &name, &curt_vers, release_as)?;
+      proj.verify_restrictions(release_as)?;
+      mono.set_by_id(id, release_as)?;
+      output.write_new(all, name.clone(), release_as.to_string())?;
+      release_as.to_string()
     } else {
       proj.verify_restrictions(&curt_vers)?;
       mono.forward_by_id(id, &curt_vers)?;
@@ -331,6 +360,72 @@ pub fn release(pref_vcs: Option<VcsRange>, all: bool, dry: bool, pause: bool) ->
   Ok(())
 }
 
+/// Ensure that a version from a `Release-As:` footer is greater than the project's current version, which it replaces.
+fn check_release_as(scheme: &dyn VersionScheme, name: &str, curt_vers: &str, release_as: &str) -> Result<()> {
+  if !scheme.less_than(curt_vers, release_as)? {
+    bail!("Release-As version {} for {} isn't greater than current version {}.", release_as, name, curt_vers);
+  }
+  Ok(())
+}
+
+pub fn promote(pref_vcs: Option<VcsRange>, id: Option<&str>, name: Option<&str>, dry: bool, pause: bool) -> Result<()> {
+  let mut mono = build(pref_vcs, VcsLevel::None, VcsLevel::Smart, VcsLevel::Local, VcsLevel::Smart)?;
+  let output = Output::new();
+  let mut output = output.release();
+
+  if let Err((should, is)) = mono.check_branch() {
+    bail!("Branch name \"{}\"\" doesn't match \"{}\".", is, should);
+  }
+
+  let id = if let Some(id) = id {
+    id.parse()?
+  } else if let Some(name) = name {
+    mono.config().find_unique(name)?.clone()
+  } else {
+    if mono.config().projects().len() != 1 {
+      bail!("No solo project.");
+    }
+    mono.config().projects().first().unwrap().id().clone()
+  };
+
+  let proj = mono.get_project(&id)?;
+  let name = proj.name().to_string();
+  let curt_vers = mono
+    .config()
+    .get_value(&id)
+    .chain_err(|| format!("Unable to find project {} value.", id))?
+    .ok_or_else(|| bad!("No such project {}.", id))?;
+
+  let target = proj.scheme().promote(&curt_vers).chain_err(|| format!("Unable to promote project {}.", id))?;
+
+  proj.verify_restrictions(&target)?;
+  mono.set_by_id(&id, &target)?;
+  output.write_changed(name, curt_vers.clone(), curt_vers, target.clone())?;
+
+  if let Some(wrote) = mono.write_promoted_changelog(&id, &target)? {
+    output.write_logged(wrote)?;
+  }
+
+  let chain_writes = mono.dependents(&id);
+  let mut final_vers = HashMap::new();
+  final_vers.insert(id, target);
+  mono.write_chains(&chain_writes, &final_vers)?;
+
+  if !dry {
+    mono.commit(true, pause)?;
+    if pause {
+      output.write_pause()?;
+    } else {
+      output.write_commit()?;
+      output.write_done()?;
+    }
+  } else {
+    output.write_dry()?;
+  }
+
+  output.commit()
+}
+
 pub fn resume(user_pref_vcs: Option<VcsRange>) -> Result<()> {
   let vcs = combine_vcs(user_pref_vcs, VcsLevel::None, VcsLevel::Smart, VcsLevel::Local, VcsLevel::Smart)?;
   let repo = Repo::open(".", VcsState::new(vcs.max(), false))?;
@@ -391,3 +486,17 @@ fn combine_vcs(
   let reqd_vcs = VcsRange::new(my_reqd_lo, my_reqd_hi);
   VcsRange::detect_and_combine(&pref_vcs, &reqd_vcs)
 }
+
+#[cfg(test)]
+mod test {
+  use super::check_release_as;
+  use crate::scheme::Scheme;
+
+  #[test]
+  fn test_check_release_as() {
+    let scheme = Scheme::SemVer.imp();
+    assert!(check_release_as(scheme, "proj", "0.0.0", "1.0.0").is_ok());
+    assert!(check_release_as(scheme, "proj", "1.2.3", "1.2.3").is_err());
+    assert!(check_release_as(scheme, "proj", "1.2.3", "1.0.0").is_err());
+  }
+}
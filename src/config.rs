@@ -3,12 +3,14 @@
 use crate::analyze::AnnotatedMark;
 use crate::either::IterEither2 as E2;
 use crate::errors::{Result, ResultExt};
-use crate::git::{FromTagBuf, Repo, Slice};
-use crate::mark::{FilePicker, LinePicker, Picker, ScanningPicker};
+use crate::git::{CommitFormat, FromTagBuf, Repo, Slice};
+use crate::mark::{FilePicker, LinePicker, Mark, Picker, ScanningPicker};
 use crate::mono::{Changelog, ChangelogEntry};
 use crate::scan::parts::{deserialize_parts, Part};
-use crate::state::{CurrentFiles, CurrentState, FilesRead, OldTags, PickPath, PrevFiles, PrevState, StateRead,
-                   StateWrite};
+use crate::state::{read_command, CurrentFiles, CurrentState, FilesRead, OldTags, PickPath, PrevFiles, PrevState,
+                   StateRead, StateWrite};
+use crate::scheme::{Scheme, VersionScheme};
+use crate::version::validate_channel;
 use chrono::prelude::Utc;
 use error_chain::bail;
 use glob::{glob_with, MatchOptions, Pattern};
@@ -23,6 +25,7 @@ use std::cmp::{Ord, Ordering};
 use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::iter::once;
+use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -37,6 +40,7 @@ pub struct ProjectId {
 impl ProjectId {
   pub fn new(id: u32, majors: Vec<u32>) -> ProjectId { ProjectId { id, majors } }
   pub fn from_id(id: u32) -> ProjectId { ProjectId { id, majors: Vec::new() } }
+  pub fn id(&self) -> u32 { self.id }
 
   fn expand(&self, sub: &SubExtent) -> ProjectId {
     assert!(self.majors.is_empty(), "ProjectId {} expanding.", self);
@@ -148,6 +152,7 @@ impl<S: StateRead> Config<S> {
   pub fn projects(&self) -> &[Project] { &self.file.projects() }
   pub fn get_project(&self, id: &ProjectId) -> Option<&Project> { self.file.get_project(id) }
   pub fn branch(&self) -> &Option<String> { self.file.branch() }
+  pub fn branch_prerelease(&self, branch: &str) -> Option<&str> { self.file.branch_prerelease(branch) }
 
   pub fn find_unique(&self, name: &str) -> Result<&ProjectId> {
     let mut iter = self.file.projects.iter().filter(|p| p.name.contains(name)).map(|p| p.id());
@@ -231,12 +236,21 @@ impl ConfigFile {
     ConfigFile::from_read(&files)
   }
 
-  fn read(data: &str) -> Result<ConfigFile> {
-    let file: ConfigFile = serde_yaml::from_str(data)?;
+  /// Parse the config file data, without expanding any of its projects.
+  pub fn read(data: &str) -> Result<ConfigFile> {
+    let mut file: ConfigFile = serde_yaml::from_str(data)?;
     file.validate()?;
+    file.inherit_options();
     Ok(file)
   }
 
+  /// Fill in per-project settings that weren't given with their global defaults.
+  fn inherit_options(&mut self) {
+    for proj in &mut self.projects {
+      proj.zero_major.get_or_insert(self.options.zero_major);
+    }
+  }
+
   fn expand<R: FilesRead>(self, read: &R) -> Result<ConfigFile> {
     let iters: Vec<_> = self.projects.into_iter().map(move |p| p.expand(read)).collect::<Result<_>>()?;
     let projects = iters.into_iter().flatten().collect();
@@ -249,6 +263,8 @@ impl ConfigFile {
   pub fn get_project(&self, id: &ProjectId) -> Option<&Project> { self.projects.iter().find(|p| p.id() == id) }
   pub fn sizes(&self) -> &HashMap<String, Size> { &self.sizes }
   pub fn branch(&self) -> &Option<String> { self.options.branch() }
+  pub fn branch_prerelease(&self, branch: &str) -> Option<&str> { self.options.prerelease(branch) }
+  pub fn commit_format(&self) -> &CommitFormat { self.options.commit_format() }
 
   pub fn hooks(&self) -> HashMap<ProjectId, (Option<&String>, &HookSet)> {
     self.projects.iter().map(|p| (p.id().clone(), (p.root(), p.hooks()))).collect()
@@ -280,6 +296,19 @@ impl ConfigFile {
         }
         prefs.insert(pref.clone());
       }
+
+      if let Some(channel) = &p.prerelease {
+        validate_channel(channel)?;
+      }
+
+      if p.version.mode() == Mode::Verify {
+        bail!("Proj {} can't have a version with mode: verify.", p.id);
+      }
+      p.version.check_format().chain_err(|| format!("Proj {} can't read back its version.", p.id))?;
+    }
+
+    for channel in self.options.prerelease.values() {
+      validate_channel(channel)?;
     }
 
     Ok(())
@@ -291,16 +320,32 @@ struct Options {
   #[serde(default = "default_prev_tag")]
   prev_tag: String,
   #[serde(default = "default_branch")]
-  branch: Option<String>
+  branch: Option<String>,
+  #[serde(default)]
+  prerelease: HashMap<String, String>,
+  #[serde(default = "default_zero_major")]
+  zero_major: ZeroMajor,
+  #[serde(default = "default_commit_format")]
+  commit_format: CommitFormat
 }
 
 impl Default for Options {
-  fn default() -> Options { Options { prev_tag: default_prev_tag(), branch: default_branch() } }
+  fn default() -> Options {
+    Options {
+      prev_tag: default_prev_tag(),
+      branch: default_branch(),
+      prerelease: Default::default(),
+      zero_major: default_zero_major(),
+      commit_format: default_commit_format()
+    }
+  }
 }
 
 impl Options {
   pub fn prev_tag(&self) -> &str { &self.prev_tag }
   pub fn branch(&self) -> &Option<String> { &self.branch }
+  pub fn prerelease(&self, branch: &str) -> Option<&str> { self.prerelease.get(branch).map(|c| c.as_str()) }
+  pub fn commit_format(&self) -> &CommitFormat { &self.commit_format }
 }
 
 fn legal_tag(prefix: &str) -> bool {
@@ -326,7 +371,15 @@ pub struct Project {
   also: Vec<Location>,
   #[serde(default, deserialize_with = "deser_labels")]
   labels: Vec<String>,
+  #[serde(default)]
+  scopes: Vec<String>,
   tag_prefix: Option<String>,
+  prerelease: Option<String>,
+  #[serde(default = "default_scheme")]
+  scheme: Scheme,
+  zero_major: Option<ZeroMajor>,
+  #[serde(default, deserialize_with = "deser_sizes")]
+  sizes: HashMap<String, Size>,
   #[serde(default)]
   subs: Option<Subs>,
   #[serde(default)]
@@ -340,13 +393,14 @@ impl Project {
   pub fn root(&self) -> Option<&String> { self.root.as_ref().and_then(|r| if r == "." { None } else { Some(r) }) }
   pub fn hooks(&self) -> &HookSet { &self.hooks }
   pub fn labels(&self) -> &[String] { &self.labels }
+  pub fn has_scope(&self, scope: &str) -> bool { self.scopes.iter().any(|s| s.eq_ignore_ascii_case(scope)) }
 
   fn annotate<S: StateRead>(&self, state: &S) -> Result<AnnotatedMark> {
     Ok(AnnotatedMark::new(self.id.clone(), self.name.clone(), self.get_value(state)?))
   }
 
   pub fn verify_restrictions(&self, vers: &str) -> Result<()> {
-    let major = Size::parts(vers)?[0];
+    let major = self.scheme().major(vers)?;
     if let Some(tag_majors) = self.tag_majors() {
       if !tag_majors.contains(&major) {
         bail!("Illegal version {} for restricted project \"{}\" with majors {:?}.", vers, self.id, tag_majors);
@@ -366,6 +420,24 @@ impl Project {
   }
 
   pub fn tag_prefix(&self) -> &Option<String> { &self.tag_prefix }
+  pub fn prerelease(&self) -> Option<&str> { self.prerelease.as_deref() }
+  pub fn scheme(&self) -> &'static dyn VersionScheme { self.scheme.imp() }
+
+  /// Adjust the size of an increment to the version, if the version is in initial development (its major part is
+  /// zero) and the project is configured to treat such versions specially.
+  pub fn adjust_size(&self, size: Size, vers: &str) -> Result<Size> {
+    let zero_major = self.zero_major.unwrap_or(ZeroMajor::Off);
+    if zero_major == ZeroMajor::Off || self.scheme().major(vers)? != 0 {
+      return Ok(size);
+    }
+
+    match (zero_major, size) {
+      (_, Size::Major) => Ok(Size::Minor),
+      (ZeroMajor::Shift, Size::Minor) => Ok(Size::Patch),
+      _ => Ok(size)
+    }
+  }
+
   pub fn tag_majors(&self) -> Option<&[u32]> { self.version.tag_majors() }
 
   pub fn write_changelog(&self, write: &mut StateWrite, cl: &Changelog, new_vers: &str) -> Result<Option<PathBuf>> {
@@ -376,20 +448,32 @@ impl Project {
     if let Some(log_path) = self.changelog().as_ref() {
       let log_path = Path::new(log_path.as_ref()).to_path_buf();
       let old_content = extract_old_content(&log_path)?;
-      write.write_file(log_path.clone(), construct_changelog_html(cl, new_vers, old_content)?, self.id())?;
+      write.write_file(log_path.clone(), construct_changelog_html(cl, new_vers, "", old_content)?, self.id())?;
       Ok(Some(log_path))
     } else {
       Ok(None)
     }
   }
 
+  /// Write a changelog section for a promoted version, which combines the sections of all of its pre-releases.
+  pub fn write_promoted_changelog(&self, write: &mut StateWrite, new_vers: &str) -> Result<Option<PathBuf>> {
+    if let Some(log_path) = self.changelog().as_ref() {
+      let log_path = Path::new(log_path.as_ref()).to_path_buf();
+      let old_content = extract_old_content(&log_path)?;
+      let (promoted, old_content) = extract_prereleases(&old_content, new_vers, self.scheme())?;
+      let html = construct_changelog_html(&Changelog::empty(), new_vers, &promoted, old_content)?;
+      write.write_file(log_path.clone(), html, self.id())?;
+      Ok(Some(log_path))
+    } else {
+      Ok(None)
+    }
+  }
+
+  /// Find the size of a commit kind, where the project's own sizes are merged over the global `parent_sizes`.
   pub fn size(&self, parent_sizes: &HashMap<String, Size>, kind: &str) -> Result<Size> {
     let kind = kind.trim();
-    parent_sizes
-      .get(kind)
-      .copied()
-      .map(Ok)
-      .unwrap_or_else(|| parent_sizes.get("*").copied().map(Ok).unwrap_or_else(|| err!("Unknown kind \"{}\".", kind)))
+    let lookup = |k: &str| self.sizes.get(k).or_else(|| parent_sizes.get(k)).copied();
+    lookup(kind).or_else(|| lookup("*")).map(Ok).unwrap_or_else(|| err!("Unknown kind \"{}\".", kind))
   }
 
   pub fn does_cover(&self, path: &str) -> Result<bool> {
@@ -421,8 +505,17 @@ impl Project {
   }
 
   pub fn check<S: StateRead>(&self, state: &S) -> Result<()> {
-    // Check that we can find the given mark.
-    self.get_value(state)?;
+    // Check that we can find the given mark, and that it's legal for our scheme.
+    let vers = self.get_value(state)?;
+    self.scheme().validate(&vers)?;
+
+    // Check that every `all` location agrees with itself, and that every `verify` location has the version.
+    for loc in &self.also {
+      loc.check_all(state, self.root())?;
+      if loc.mode() == Mode::Verify {
+        loc.verify_value(state, self.root(), &vers)?;
+      }
+    }
 
     self.check_excludes()?;
 
@@ -514,7 +607,12 @@ impl Project {
         version: expand_version(&self.version, &sub),
         also: expand_also(&self.also),
         labels: Default::default(),
+        scopes: self.scopes.clone(),
         tag_prefix: self.tag_prefix.clone(),
+        prerelease: self.prerelease.clone(),
+        scheme: self.scheme,
+        zero_major: self.zero_major,
+        sizes: self.sizes.clone(),
         subs: None,
         hooks: self.hooks.clone()
       })))
@@ -751,7 +849,8 @@ impl SubExtent {
 // #[serde(untagged)]
 enum Location {
   File(FileLocation),
-  Tag(TagLocation)
+  Tag(TagLocation),
+  Command(CommandLocation)
 }
 
 impl Location {
@@ -759,27 +858,62 @@ impl Location {
 
   pub fn tag_majors(&self) -> Option<&[u32]> {
     match self {
-      Location::File(_) => None,
-      Location::Tag(tagl) => tagl.majors()
+      Location::Tag(tagl) => tagl.majors(),
+      _ => None
     }
   }
 
   pub fn write_value(&self, write: &mut StateWrite, root: Option<&String>, vers: &str, id: &ProjectId) -> Result<()> {
     match self {
       Location::File(l) => l.write_value(write, root, vers, id),
-      Location::Tag(_) => Ok(())
+      Location::Tag(_) => Ok(()),
+      Location::Command(l) => l.write_value(write, root, vers, id)
     }
   }
 
   pub fn read_value<S: StateRead>(&self, read: &S, root: Option<&String>, proj: &ProjectId) -> Result<String> {
     match self {
       Location::File(l) => l.read_value(read, root),
-      Location::Tag(l) => l.read_value(read, proj)
+      Location::Tag(l) => l.read_value(read, proj),
+      Location::Command(l) => l.read_value(read, root, proj)
+    }
+  }
+
+  /// Ensure that a location which matches multiple values has only a single value among them.
+  pub fn check_all<S: StateRead>(&self, read: &S, root: Option<&String>) -> Result<()> {
+    match self {
+      Location::File(l) if l.all => l.read_value(read, root).map(|_| ()),
+      _ => Ok(())
     }
   }
 
   pub fn is_tag(&self) -> bool { matches!(self, Location::Tag(..)) }
 
+  /// Ensure that any `format` of the location keeps the whole version, as is needed to read it back exactly.
+  pub fn check_format(&self) -> Result<()> {
+    match self {
+      Location::File(l) => l.check_format(),
+      _ => Ok(())
+    }
+  }
+
+  pub fn mode(&self) -> Mode {
+    match self {
+      Location::File(l) => l.mode,
+      Location::Tag(_) => Mode::Write,
+      Location::Command(l) => l.mode
+    }
+  }
+
+  /// Ensure that a `verify` location has the given version.
+  pub fn verify_value<S: StateRead>(&self, read: &S, root: Option<&String>, vers: &str) -> Result<()> {
+    match self {
+      Location::File(l) => l.verify_value(read, root, vers),
+      Location::Tag(_) => Ok(()),
+      Location::Command(l) => l.verify_value(root, vers)
+    }
+  }
+
   #[cfg(test)]
   pub fn picker(&self) -> &Picker {
     match self {
@@ -820,6 +954,9 @@ impl<'de> Deserialize<'de> for Location {
         let mut tags: Option<TagSpec> = None;
         let mut code: Option<String> = None;
         let mut format: Option<String> = None;
+        let mut all = false;
+        let mut command: Option<CommandSpec> = None;
+        let mut mode: Option<Mode> = None;
 
         while let Some(key) = map.next_key::<String>()? {
           match key.as_str() {
@@ -829,7 +966,7 @@ impl<'de> Deserialize<'de> for Location {
             "tags" => {
               tags = Some(map.next_value()?);
             }
-            "json" | "yaml" | "toml" | "xml" => {
+            "json" | "jsonc" | "yaml" | "toml" | "xml" | "ini" | "hcl" | "gradle" => {
               code = Some(key);
               parts = Some(map.next_value_seed(VecPartSeed)?);
             }
@@ -839,40 +976,71 @@ impl<'de> Deserialize<'de> for Location {
             "format" => {
               format = Some(map.next_value()?);
             }
+            "all" => {
+              all = map.next_value()?;
+            }
+            "command" => {
+              command = Some(map.next_value()?);
+            }
+            "mode" => {
+              mode = Some(map.next_value()?);
+            }
             other => return Err(de::Error::invalid_value(Unexpected::Str(other), &"a location key"))
           }
         }
 
-        if let Some(file) = file {
+        if let Some(command) = command {
+          if file.is_some() || tags.is_some() {
+            Err(de::Error::custom("can't have 'file' or 'tags' in 'command' location"))
+          } else if format.is_some() || all {
+            Err(de::Error::custom("can't have 'format' or 'all' in 'command' location"))
+          } else {
+            Ok(Location::Command(CommandLocation { command, mode: mode.unwrap_or_default() }))
+          }
+        } else if let Some(file) = file {
           if tags.is_some() {
             Err(de::Error::custom("cant have both 'file' and 'tags' for location"))
           } else if pattern.is_none() && parts.is_none() {
-            Ok(Location::File(FileLocation { file, format, picker: Picker::File(FilePicker {}) }))
+            if all {
+              Err(de::Error::custom("can't have 'all' without 'pattern' or parts field"))
+            } else {
+              let picker = Picker::File(FilePicker {});
+              Ok(Location::File(FileLocation { file, format, all, mode: mode.unwrap_or_default(), picker }))
+            }
           } else if let Some(pattern) = pattern {
             if parts.is_some() {
               Err(de::Error::custom("can't have both 'pattern' and parts field"))
             } else {
-              Ok(Location::File(FileLocation { file, format, picker: Picker::Line(LinePicker::new(pattern)) }))
+              let picker = Picker::Line(LinePicker::new(pattern));
+              Ok(Location::File(FileLocation { file, format, all, mode: mode.unwrap_or_default(), picker }))
             }
           } else {
             let parts = parts.unwrap();
-            let loc = match code.unwrap().as_str() {
-              "json" => Location::File(FileLocation { file, format, picker: Picker::Json(ScanningPicker::new(parts)) }),
-              "yaml" => Location::File(FileLocation { file, format, picker: Picker::Yaml(ScanningPicker::new(parts)) }),
-              "toml" => Location::File(FileLocation { file, format, picker: Picker::Toml(ScanningPicker::new(parts)) }),
-              "xml" => Location::File(FileLocation { file, format, picker: Picker::Xml(ScanningPicker::new(parts)) }),
+            let picker = match code.unwrap().as_str() {
+              "json" => Picker::Json(ScanningPicker::new(parts)),
+              "jsonc" => Picker::Jsonc(ScanningPicker::new(parts)),
+              "yaml" => Picker::Yaml(ScanningPicker::new(parts)),
+              "toml" => Picker::Toml(ScanningPicker::new(parts)),
+              "xml" => Picker::Xml(ScanningPicker::new(parts)),
+              "ini" => Picker::Ini(ScanningPicker::new(parts)),
+              "hcl" => Picker::Hcl(ScanningPicker::new(parts)),
+              "gradle" => Picker::Gradle(ScanningPicker::new(parts)),
               other => return Err(de::Error::custom(format!("unrecognized part {}", other)))
             };
-            Ok(loc)
+            Ok(Location::File(FileLocation { file, format, all, mode: mode.unwrap_or_default(), picker }))
           }
         } else if let Some(tags) = tags {
           if format.is_some() {
             Err(de::Error::custom("cant have 'format' in 'tags' location"))
+          } else if all {
+            Err(de::Error::custom("cant have 'all' in 'tags' location"))
+          } else if mode.is_some() {
+            Err(de::Error::custom("cant have 'mode' in 'tags' location"))
           } else {
             Ok(Location::Tag(TagLocation { tags }))
           }
         } else {
-          Err(de::Error::custom("must have 'file' or 'tags' for location"))
+          Err(de::Error::custom("must have 'file', 'tags', or 'command' for location"))
         }
       }
     }
@@ -894,6 +1062,48 @@ impl TagLocation {
   }
 }
 
+#[derive(Clone, Debug)]
+struct CommandLocation {
+  command: CommandSpec,
+  mode: Mode
+}
+
+#[derive(Clone, Deserialize, Debug)]
+struct CommandSpec {
+  read: String,
+  write: String
+}
+
+impl CommandLocation {
+  /// Run the read command to get the version. Since a command can't see a previous state, the previous version is
+  /// instead the latest tag of the project, if there is one.
+  fn read_value<S: StateRead>(&self, read: &S, root: Option<&String>, proj: &ProjectId) -> Result<String> {
+    if !read.is_current() {
+      if let Some(tag) = read.latest_tag(proj) {
+        return Ok(tag.clone());
+      }
+    }
+
+    read_command(&self.command.read, root)
+  }
+
+  fn write_value(&self, write: &mut StateWrite, root: Option<&String>, vers: &str, id: &ProjectId) -> Result<()> {
+    match self.mode {
+      Mode::Write => write.run_command(&self.command.write, root, vers, id),
+      Mode::Verify => write.verify_command(&self.command.read, root, vers),
+      Mode::Read => Ok(())
+    }
+  }
+
+  fn verify_value(&self, root: Option<&String>, vers: &str) -> Result<()> {
+    let found = read_command(&self.command.read, root)?;
+    if found != vers {
+      bail!("Read command {} output {}, expected {}.", self.command.read, found, vers);
+    }
+    Ok(())
+  }
+}
+
 #[derive(Clone, Deserialize, Debug)]
 #[serde(untagged)]
 enum TagSpec {
@@ -934,35 +1144,147 @@ impl MajorTagSpec {
   pub fn majors(&self) -> &[u32] { &self.majors }
 }
 
+/// How a location is used: `read` locations are never written, and `verify` locations are never written, but must
+/// match the version.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum Mode {
+  Read,
+  #[default]
+  Write,
+  Verify
+}
+
 #[derive(Clone, Deserialize, Debug)]
 struct FileLocation {
   file: String,
   #[serde(flatten)]
   picker: Picker,
-  format: Option<String>
+  format: Option<String>,
+  #[serde(default)]
+  all: bool,
+  #[serde(default)]
+  mode: Mode
 }
 
 impl FileLocation {
   pub fn write_value(&self, write: &mut StateWrite, root: Option<&String>, vers: &str, id: &ProjectId) -> Result<()> {
     let file = self.rooted(root);
     let val = self.format_vers(vers)?;
-    write.update_mark(PickPath::new(file, self.picker.clone()), val, id)
+    match self.mode {
+      Mode::Write => write.update_mark(PickPath::new(file, self.picker.clone(), self.all), val, id),
+      Mode::Verify => write.verify_mark(PickPath::new(file, self.picker.clone(), self.all), val),
+      Mode::Read => Ok(())
+    }
+  }
+
+  /// Check that every match of this location is the version, as it would be written.
+  fn verify_value<S: StateRead>(&self, read: &S, root: Option<&String>, vers: &str) -> Result<()> {
+    let file = self.rooted(root);
+    let data: String = read.read_file(&file)?;
+    let val = self.format_vers(vers)?;
+    let marks = if self.all { self.picker.find_all(&data)? } else { vec![self.picker.find(&data)?] };
+    if let Some(found) = marks.iter().find(|m| m.value() != val) {
+      bail!("Found {} in {}, expected {}.", found.value(), file.to_string_lossy(), val);
+    }
+    Ok(())
   }
 
   fn format_vers(&self, vers: &str) -> Result<String> {
-    if let Some(format) = &self.format {
-      let tmpl = ParserBuilder::with_stdlib().build()?.parse(format)?;
-      let globals = liquid::object!({ "v": vers });
-      Ok(tmpl.render(&globals)?)
-    } else {
-      Ok(vers.to_string())
+    match &self.format {
+      Some(format) => render_format(format, vers),
+      None => Ok(vers.to_string())
+    }
+  }
+
+  /// Recover the version from a value that was written with the format.
+  ///
+  /// The format is rendered with a sample version, and the rendered sample becomes a pattern to match the value. If
+  /// the whole sample survives the format, the version is whatever stands in its place; otherwise, each of its major,
+  /// minor, and patch parts that survive are matched as numbers, and any missing parts become zero. A pre-release
+  /// after the patch is recovered if the format keeps it, and only its separators are changed.
+  fn unformat_vers(&self, val: &str) -> Result<String> {
+    let format = match &self.format {
+      Some(format) => format,
+      None => return Ok(val.to_string())
+    };
+
+    let sample = FORMAT_SAMPLE.join(".");
+    let rendered = render_format(format, &sample)?;
+    let no_match = || bad!("Value \"{}\" doesn't match format \"{}\".", val, format);
+
+    if let Some(i) = rendered.find(&sample) {
+      let (before, after) = (&rendered[.. i], &rendered[i + sample.len() ..]);
+      let pattern = Regex::new(&format!("^{}(.+){}$", escape(before), escape(after)))?;
+      let caps = pattern.captures(val).ok_or_else(no_match)?;
+      return Ok(caps[1].to_string());
+    }
+
+    let pre_seps = pre_separators(format)?;
+    let mut pattern = String::from("^");
+    let mut found_any = false;
+    let mut rest = rendered.as_str();
+    let next = |rest: &str| FORMAT_SAMPLE.iter().enumerate().filter_map(|(p, s)| rest.find(s).map(|i| (i, p))).min();
+    while let Some((i, part)) = next(rest) {
+      pattern.push_str(&escape(&rest[.. i]));
+      pattern.push_str(&format!("(?P<p{}>[0-9]+)", part));
+      if let (2, Some((sep, dot))) = (part, &pre_seps) {
+        pattern.push_str(&format!("(?:{}(?P<pre>[0-9A-Za-z-]+(?:{}[0-9A-Za-z-]+)*))?", escape(sep), escape(dot)));
+      }
+      found_any = true;
+      rest = &rest[i + FORMAT_SAMPLE[part].len() ..];
+    }
+    pattern.push_str(&escape(rest));
+    pattern.push('$');
+
+    if !found_any {
+      bail!("Format \"{}\" doesn't include the version.", format);
+    }
+
+    let caps = Regex::new(&pattern)?.captures(val).ok_or_else(no_match)?;
+    let parts: Vec<_> = (0 .. 3).map(|p| caps.name(&format!("p{}", p)).map(|m| m.as_str()).unwrap_or("0")).collect();
+    let mut vers = parts.join(".");
+    if let (Some(pre), Some((_, dot))) = (caps.name("pre"), &pre_seps) {
+      vers.push('-');
+      vers.push_str(&pre.as_str().split(dot.as_str()).collect::<Vec<_>>().join("."));
+    }
+
+    if render_format(format, &vers)? != val {
+      return Err(no_match());
+    }
+    Ok(vers)
+  }
+
+  /// Check that the full version, including any pre-release, can be recovered from a value written with the format.
+  fn check_format(&self) -> Result<()> {
+    let format = match &self.format {
+      Some(format) => format,
+      None => return Ok(())
+    };
+
+    let sample = format!("{}-{}", FORMAT_SAMPLE.join("."), FORMAT_PRE.join("."));
+    match self.unformat_vers(&render_format(format, &sample)?) {
+      Ok(vers) if vers == sample => Ok(()),
+      _ => bail!("Format \"{}\" doesn't keep the whole version.", format)
     }
   }
 
   pub fn read_value<S: StateRead>(&self, read: &S, root: Option<&String>) -> Result<String> {
     let file = self.rooted(root);
     let data: String = read.read_file(&file)?;
-    self.picker.find(&data).map(|m| m.into_value())
+    let vers = if self.all {
+      let mut marks = self.picker.find_all(&data)?.into_iter().map(|m| m.into_value());
+      let first = marks.next().ok_or_else(|| bad!("No match in {}", file.to_string_lossy()))?;
+      if let Some(other) = marks.find(|v| v != &first) {
+        bail!("Mismatched values in {}: found both {} and {}", file.to_string_lossy(), first, other);
+      }
+      self.unformat_vers(&first)
+    } else {
+      self.unformat_vers(&self.picker.find(&data)?.into_value())
+    }?;
+
+    Mark::new(vers.clone(), 0).validate_version()?;
+    Ok(vers)
   }
 
   pub fn rooted(&self, root: Option<&String>) -> PathBuf {
@@ -973,6 +1295,27 @@ impl FileLocation {
   }
 }
 
+/// The major, minor, and patch parts of the sample version that `format` templates are matched against.
+const FORMAT_SAMPLE: [&str; 3] = ["1000001", "1000002", "1000003"];
+
+/// The pre-release identifiers of the sample version, which find how a format renders a pre-release.
+const FORMAT_PRE: [&str; 2] = ["1000004", "1000005"];
+
+/// Find how a format renders the separators of a pre-release: the one before the pre-release, and the one between
+/// its identifiers. This is `None` if the format doesn't keep the pre-release right after the patch number.
+fn pre_separators(format: &str) -> Result<Option<(String, String)>> {
+  let sample = format!("{}-{}", FORMAT_SAMPLE.join("."), FORMAT_PRE.join("."));
+  let rendered = render_format(format, &sample)?;
+  let seps = Regex::new(&format!("{}(.+?){}(.+?){}", FORMAT_SAMPLE[2], FORMAT_PRE[0], FORMAT_PRE[1]))?;
+  Ok(seps.captures(&rendered).map(|c| (c[1].to_string(), c[2].to_string())))
+}
+
+fn render_format(format: &str, vers: &str) -> Result<String> {
+  let tmpl = ParserBuilder::with_stdlib().build()?.parse(format)?;
+  let globals = liquid::object!({ "v": vers });
+  Ok(tmpl.render(&globals)?)
+}
+
 #[derive(Deserialize, Debug)]
 struct Subs {
   #[serde(default)]
@@ -1016,6 +1359,10 @@ pub enum Size {
 
 impl Size {
   fn is_size(v: &str) -> bool { Size::from_str(v).is_ok() }
+}
+
+impl FromStr for Size {
+  type Err = crate::errors::Error;
 
   fn from_str(v: &str) -> Result<Size> {
     match v {
@@ -1028,40 +1375,19 @@ impl Size {
       other => err!("Unknown size: {}", other)
     }
   }
+}
 
-  pub fn parts(v: &str) -> Result<[u32; 3]> {
-    let parts: Vec<_> = v
-      .split('.')
-      .map(|p| p.parse())
-      .collect::<std::result::Result<_, _>>()
-      .chain_err(|| format!("Couldn't split {} into parts", v))?;
-    if parts.len() != 3 {
-      return err!("Not a 3-part version: {}", v);
-    }
-    Ok([parts[0], parts[1], parts[2]])
-  }
-
-  pub fn less_than(v1: &str, v2: &str) -> Result<bool> {
-    let p1 = Size::parts(v1)?;
-    let p2 = Size::parts(v2)?;
-
-    Ok(p1[0] < p2[0] || (p1[0] == p2[0] && (p1[1] < p2[1] || (p1[1] == p2[1] && p1[2] < p2[2]))))
-  }
-
-  pub fn apply(self, v: &str) -> Result<String> {
-    let parts = Size::parts(v)?;
-
-    let newv = match self {
-      Size::Major => format!("{}.{}.{}", parts[0] + 1, 0, 0),
-      Size::Minor => format!("{}.{}.{}", parts[0], parts[1] + 1, 0),
-      Size::Patch => format!("{}.{}.{}", parts[0], parts[1], parts[2] + 1),
-      Size::None => format!("{}.{}.{}", parts[0], parts[1], parts[2]),
-      Size::Empty => format!("{}.{}.{}", parts[0], parts[1], parts[2]),
-      Size::Fail => bail!("'fail' size encountered.")
-    };
-
-    Ok(newv)
-  }
+/// How increments are treated for a version in initial development, when its major part is zero (as in `0.y.z`).
+///
+/// - **Off**: Increments are the same as for any other version.
+/// - **BreakingMinor**: A major increment increments the minor part instead, so `0.3.2` becomes `0.4.0`.
+/// - **Shift**: Major increments are treated as minor, and minor increments are treated as patch.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ZeroMajor {
+  Off,
+  BreakingMinor,
+  Shift
 }
 
 impl fmt::Display for Size {
@@ -1119,6 +1445,10 @@ impl Ord for Size {
 fn default_includes() -> Vec<String> { vec!["**/*".into()] }
 fn default_prev_tag() -> String { "versio-prev".into() }
 fn default_branch() -> Option<String> { None }
+fn default_scheme() -> Scheme { Scheme::SemVer }
+fn default_zero_major() -> ZeroMajor { ZeroMajor::Off }
+
+fn default_commit_format() -> CommitFormat { CommitFormat::Conventional }
 
 fn deser_labels<'de, D: Deserializer<'de>>(desr: D) -> std::result::Result<Vec<String>, D::Error> {
   struct StringsVisitor;
@@ -1226,7 +1556,50 @@ fn extract_old_content(path: &Path) -> Result<String> {
   Ok(content)
 }
 
-fn construct_changelog_html(cl: &Changelog, new_vers: &str, old_content: String) -> Result<String> {
+/// Pull the contents of all sections of the old changelog content that are pre-releases of the given version,
+/// returning those contents and the remaining old content.
+fn extract_prereleases(old_content: &str, new_vers: &str, scheme: &dyn VersionScheme) -> Result<(String, String)> {
+  let head = Regex::new(r"Release ([^ ]+) : ")?;
+
+  let mut sections: Vec<Vec<&str>> = vec![Vec::new()];
+  for line in old_content.split('\n') {
+    if line.contains("### VERSIO CONTENT ") {
+      sections.push(Vec::new());
+    }
+    sections.last_mut().unwrap().push(line);
+  }
+
+  let mut promoted: Vec<&str> = Vec::new();
+  let mut kept = Vec::new();
+  for section in sections.into_iter().filter(|s| !s.is_empty()) {
+    let is_pre = section
+      .iter()
+      .find_map(|l| head.captures(l))
+      .and_then(|c| scheme.promote(&c[1]).ok())
+      .map(|v| v == new_vers)
+      .unwrap_or(false);
+
+    match section_contents(&section).filter(|_| is_pre) {
+      Some(range) => promoted.extend_from_slice(&section[range]),
+      None => kept.extend(section)
+    }
+  }
+
+  let promoted = promoted.iter().map(|l| format!("\n{}", l)).collect();
+  Ok((promoted, kept.join("\n")))
+}
+
+/// Find the range of lines between a changelog section's begin and end markers.
+fn section_contents(section: &[&str]) -> Option<Range<usize>> {
+  let start = section.iter().position(|l| l.contains("### VERSIO BEGIN SECTION ###"))? + 1;
+  let end = section.iter().position(|l| l.contains("### VERSIO END SECTION ###"))?;
+  if end < start {
+    return None;
+  }
+  Some(start .. end)
+}
+
+fn construct_changelog_html(cl: &Changelog, new_vers: &str, promoted: &str, old_content: String) -> Result<String> {
   let tmpl = include_str!("tmpl/changelog.liquid");
   let tmpl = ParserBuilder::with_stdlib().build()?.parse(tmpl)?;
   let nowymd = Utc::now().format("%Y-%m-%d").to_string();
@@ -1295,6 +1668,7 @@ fn construct_changelog_html(cl: &Changelog, new_vers: &str, old_content: String)
       "date": nowymd,
       "prs": prs,
       "deps": dps,
+      "promoted": promoted,
       "version": new_vers
     },
     "old_content": old_content,
@@ -1306,8 +1680,13 @@ fn construct_changelog_html(cl: &Changelog, new_vers: &str, old_content: String)
 
 #[cfg(test)]
 mod test {
-  use super::{ConfigFile, FileLocation, HashMap, Location, Picker, Project, ProjectId, ScanningPicker, Size};
+  use super::{extract_prereleases, ConfigFile, FileLocation, HashMap, Location, Mode, Picker, Project, ProjectId,
+              ScanningPicker, Size};
+  use crate::errors::Result;
   use crate::scan::parts::Part;
+  use crate::scheme::Scheme;
+  use crate::state::{FilesRead, StateRead};
+  use std::path::Path;
 
   #[test]
   fn test_both_file_and_tags() {
@@ -1323,6 +1702,194 @@ projects:
     assert!(ConfigFile::read(data).is_err())
   }
 
+  #[test]
+  fn test_all_mismatch() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      file: "README.md"
+      pattern: "thing@(\\d+\\.\\d+\\.\\d+)"
+      all: true"#;
+
+    let config = ConfigFile::read(data).unwrap();
+    let proj = &config.projects()[0];
+
+    let same = OneFile("Install `npm i thing@1.2.3` or `yarn add thing@1.2.3`.");
+    assert_eq!("1.2.3", proj.get_value(&same).unwrap());
+
+    let different = OneFile("Install `npm i thing@1.2.3` or `yarn add thing@1.2.4`.");
+    assert!(proj.get_value(&different).is_err());
+  }
+
+  #[test]
+  fn test_all_tags() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      tags:
+        default: "1.0.0"
+      all: true"#;
+
+    assert!(ConfigFile::read(data).is_err())
+  }
+
+  #[test]
+  fn test_command() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      command:
+        read: "echo ' 1.2.3 '"
+        write: "echo $VERSIO_NEW_VERSION > version.txt""#;
+
+    let config = ConfigFile::read(data).unwrap();
+    let proj = &config.projects()[0];
+    assert_eq!("1.2.3", proj.get_value(&OneFile("")).unwrap());
+
+    let with_file = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      file: "version.txt"
+      command:
+        read: "cat version.txt"
+        write: "true""#;
+
+    assert!(ConfigFile::read(with_file).is_err());
+  }
+
+  #[test]
+  fn test_format() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      file: "README.md"
+      pattern: "release-(?P<version>[0-9_]+)"
+      format: "{{ v | replace: '.', '_' }}"
+    also:
+      - file: "README.md"
+        pattern: "thing-(\\S+)-linux"
+        format: "v{{v}}"
+      - file: "README.md"
+        pattern: "short (\\S+)"
+        format: "{% assign a = v | split: '.' %}{{a[0]}}.{{a[1]}}""#;
+
+    let config = ConfigFile::read(data).unwrap();
+    let proj = &config.projects()[0];
+
+    let read = OneFile("release-1_2_3 with thing-v1.2.3-rc.1-linux or short 1.2");
+    assert_eq!("1.2.3", proj.get_value(&read).unwrap());
+
+    let also: Vec<_> = proj.also.iter().map(|l| l.read_value(&read, None, proj.id()).unwrap()).collect();
+    assert_eq!(vec!["1.2.3-rc.1", "1.2.0"], also);
+
+    assert!(proj.get_value(&OneFile("release-1.2.3")).is_err());
+  }
+
+  #[test]
+  fn test_format_pre() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      file: "README.md"
+      pattern: "release-(?P<version>[0-9A-Za-z_-]+)"
+      format: "{{ v | replace: '.', '_' }}""#;
+
+    let config = ConfigFile::read(data).unwrap();
+    let proj = &config.projects()[0];
+    assert_eq!("1.2.3-rc.1", proj.get_value(&OneFile("release-1_2_3-rc_1")).unwrap());
+    assert_eq!("1.2.3", proj.get_value(&OneFile("release-1_2_3")).unwrap());
+  }
+
+  #[test]
+  fn test_format_lossy_version() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      file: "README.md"
+      pattern: "short (\\S+)"
+      format: "{% assign a = v | split: '.' %}{{a[0]}}.{{a[1]}}""#;
+
+    assert!(ConfigFile::read(data).is_err());
+  }
+
+  #[test]
+  fn test_verify() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      file: "README.md"
+      pattern: "thing (\\S+),"
+    also:
+      - file: "README.md"
+        pattern: "docs v(\\S+)"
+        format: "{{v}}-docs"
+        mode: verify"#;
+
+    let config = ConfigFile::read(data).unwrap();
+    let proj = &config.projects()[0];
+    assert_eq!(Mode::Verify, proj.also[0].mode());
+
+    assert!(proj.check(&OneFile("thing 1.2.3, docs v1.2.3-docs")).is_ok());
+    assert!(proj.check(&OneFile("thing 1.2.3, docs v1.2.2-docs")).is_err());
+  }
+
+  #[test]
+  fn test_verify_version() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      file: "README.md"
+      pattern: "thing (\\S+),"
+      mode: verify"#;
+
+    assert!(ConfigFile::read(data).is_err());
+  }
+
+  #[test]
+  fn test_mode_tags() {
+    let data = r#"
+projects:
+  - name: everything
+    id: 1
+    version:
+      tags:
+        default: "1.0.0"
+      mode: read"#;
+
+    assert!(ConfigFile::read(data).is_err())
+  }
+
+  struct OneFile(&'static str);
+
+  impl FilesRead for OneFile {
+    fn has_file(&self, _: &Path) -> Result<bool> { Ok(true) }
+    fn read_file(&self, _: &Path) -> Result<String> { Ok(self.0.to_string()) }
+    fn subdirs(&self, _: Option<&String>, _: &str) -> Result<Vec<String>> { Ok(Vec::new()) }
+  }
+
+  impl StateRead for OneFile {
+    fn latest_tag(&self, _: &ProjectId) -> Option<&String> { None }
+    fn is_current(&self) -> bool { true }
+  }
+
   #[test]
   fn test_scan() {
     let data = r#"
@@ -1474,6 +2041,35 @@ sizes:
     assert_eq!(&Size::None, config.sizes.get("none").unwrap());
   }
 
+  #[test]
+  fn test_project_sizes() {
+    let config = r#"
+projects:
+  - name: docs
+    id: 1
+    version: { file: f1 }
+    sizes:
+      minor: [ docs ]
+      fail: [ "-" ]
+
+  - name: lib
+    id: 2
+    version: { file: f2 }
+sizes:
+  use_angular: true
+  patch: [ "*" ]
+"#;
+
+    let config = ConfigFile::read(config).unwrap();
+    let (docs, lib) = (&config.projects[0], &config.projects[1]);
+    assert_eq!(Size::Minor, docs.size(&config.sizes, "docs").unwrap());
+    assert_eq!(Size::None, lib.size(&config.sizes, "docs").unwrap());
+    assert_eq!(Size::Minor, docs.size(&config.sizes, "feat").unwrap());
+    assert_eq!(Size::Fail, docs.size(&config.sizes, "-").unwrap());
+    assert_eq!(Size::Patch, lib.size(&config.sizes, "-").unwrap());
+    assert_eq!(Size::Patch, docs.size(&config.sizes, "other").unwrap());
+  }
+
   #[test]
   fn test_sizes_dup() {
     let config = r#"
@@ -1488,6 +2084,154 @@ sizes:
     assert!(ConfigFile::read(config).is_err());
   }
 
+  #[test]
+  fn test_prerelease() {
+    let config = r#"
+options:
+  prerelease:
+    next: beta
+projects:
+  - name: p1
+    id: 1
+    prerelease: rc
+    version: { file: f1 }
+    "#;
+
+    let config = ConfigFile::read(config).unwrap();
+    assert_eq!(Some("beta"), config.branch_prerelease("next"));
+    assert_eq!(None, config.branch_prerelease("main"));
+    assert_eq!(Some("rc"), config.projects[0].prerelease());
+  }
+
+  #[test]
+  fn test_scheme() {
+    let config = r#"
+projects:
+  - name: p1
+    id: 1
+    version: { file: f1 }
+
+  - name: p2
+    id: 2
+    scheme: pep440
+    version: { file: f2 }
+    "#;
+
+    let config = ConfigFile::read(config).unwrap();
+    assert_eq!(Scheme::SemVer, config.projects[0].scheme);
+    assert_eq!(Scheme::Pep440, config.projects[1].scheme);
+  }
+
+  #[test]
+  fn test_zero_major() {
+    let config = r#"
+options:
+  zero_major: breaking_minor
+projects:
+  - name: p1
+    id: 1
+    version: { file: f1 }
+
+  - name: p2
+    id: 2
+    zero_major: shift
+    version: { file: f2 }
+    "#;
+
+    let config = ConfigFile::read(config).unwrap();
+    let (p1, p2) = (&config.projects[0], &config.projects[1]);
+    assert_eq!(Size::Minor, p1.adjust_size(Size::Major, "0.3.2").unwrap());
+    assert_eq!(Size::Minor, p1.adjust_size(Size::Minor, "0.3.2").unwrap());
+    assert_eq!(Size::Major, p1.adjust_size(Size::Major, "1.3.2").unwrap());
+    assert_eq!(Size::Minor, p2.adjust_size(Size::Major, "0.3.2").unwrap());
+    assert_eq!(Size::Patch, p2.adjust_size(Size::Minor, "0.3.2").unwrap());
+    assert_eq!(Size::Minor, p2.adjust_size(Size::Minor, "1.3.2").unwrap());
+  }
+
+  #[test]
+  fn test_commit_format() {
+    let config = r#"
+options:
+  commit_format: gitmoji
+projects: []
+"#;
+
+    let config = ConfigFile::read(config).unwrap();
+    assert_eq!("feat", config.commit_format().kind(":sparkles: add thing"));
+
+    let config = r#"
+options:
+  commit_format:
+    pattern: "^(?P<kind>\\w+) -"
+projects: []
+"#;
+
+    let config = ConfigFile::read(config).unwrap();
+    assert_eq!("add", config.commit_format().kind("ADD - a thing"));
+  }
+
+  #[test]
+  fn test_commit_format_bad() {
+    let config = r#"
+options:
+  commit_format:
+    pattern: "^(?P<type>\\w+) -"
+projects: []
+"#;
+
+    assert!(ConfigFile::read(config).is_err());
+  }
+
+  #[test]
+  fn test_validate_prerelease() {
+    let config = r#"
+projects:
+  - name: p1
+    id: 1
+    prerelease: "beta.1"
+    version: { file: f1 }
+    "#;
+
+    assert!(ConfigFile::read(config).is_err());
+  }
+
+  #[test]
+  fn test_extract_prereleases() {
+    let old = r#"<!-- ### VERSIO CONTENT 2020-06-02 ### -->
+<div class="release">
+  <div class="release-head"><span class="caret caret-down"></span>Release 1.3.0-rc.2 : 2020-06-02</div>
+  <div class="nested active">
+    <!-- ### VERSIO BEGIN SECTION ### -->
+    <div class="pr">rc2 pr</div>
+    <!-- ### VERSIO END SECTION ### -->
+  </div>
+</div>
+<!-- ### VERSIO CONTENT 2020-06-01 ### -->
+<div class="release">
+  <div class="release-head"><span class="caret caret-down"></span>Release 1.3.0-rc.1 : 2020-06-01</div>
+  <div class="nested active">
+    <!-- ### VERSIO BEGIN SECTION ### -->
+    <div class="pr">rc1 pr</div>
+    <!-- ### VERSIO END SECTION ### -->
+  </div>
+</div>
+<!-- ### VERSIO CONTENT 2020-05-01 ### -->
+<div class="release">
+  <div class="release-head"><span class="caret caret-down"></span>Release 1.2.0 : 2020-05-01</div>
+  <div class="nested active">
+    <!-- ### VERSIO BEGIN SECTION ### -->
+    <div class="pr">final pr</div>
+    <!-- ### VERSIO END SECTION ### -->
+  </div>
+</div>"#;
+
+    let (promoted, kept) = extract_prereleases(old, "1.3.0", Scheme::SemVer.imp()).unwrap();
+    assert_eq!("\n    <div class=\"pr\">rc2 pr</div>\n    <div class=\"pr\">rc1 pr</div>", promoted);
+    assert!(kept.starts_with("<!-- ### VERSIO CONTENT 2020-05-01 ### -->"));
+    assert!(kept.contains("final pr"));
+    assert!(!kept.contains("rc1 pr"));
+  }
+
   #[test]
   fn test_include_w_root() {
     let proj = Project {
@@ -1501,11 +2245,18 @@ sizes:
       version: Location::File(FileLocation {
         file: "package.json".into(),
         picker: Picker::Json(ScanningPicker::new(vec![Part::Map("version".into())])),
-        format: None
+        format: None,
+        all: false,
+        mode: Mode::Write
       }),
       also: Vec::new(),
       tag_prefix: None,
+      prerelease: None,
+      scheme: Scheme::SemVer,
+      zero_major: None,
+      sizes: Default::default(),
       labels: Default::default(),
+      scopes: Default::default(),
       hooks: Default::default(),
       subs: None
     };
@@ -1527,11 +2278,18 @@ sizes:
       version: Location::File(FileLocation {
         file: "package.json".into(),
         picker: Picker::Json(ScanningPicker::new(vec![Part::Map("version".into())])),
-        format: None
+        format: None,
+        all: false,
+        mode: Mode::Write
       }),
       also: Vec::new(),
       tag_prefix: None,
+      prerelease: None,
+      scheme: Scheme::SemVer,
+      zero_major: None,
+      sizes: Default::default(),
       labels: Default::default(),
+      scopes: Default::default(),
       hooks: Default::default(),
       subs: None
     };
@@ -1552,11 +2310,18 @@ sizes:
       version: Location::File(FileLocation {
         file: "package.json".into(),
         picker: Picker::Json(ScanningPicker::new(vec![Part::Map("version".into())])),
-        format: None
+        format: None,
+        all: false,
+        mode: Mode::Write
       }),
       also: Vec::new(),
       tag_prefix: None,
+      prerelease: None,
+      scheme: Scheme::SemVer,
+      zero_major: None,
+      sizes: Default::default(),
       labels: Default::default(),
+      scopes: Default::default(),
       hooks: Default::default(),
       subs: None
     };
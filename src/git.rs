@@ -13,6 +13,7 @@ use git2::{AnnotatedCommit, AutotagOption, Blob, Commit, Cred, CredentialType, D
            RepositoryOpenFlags, RepositoryState, ResetType, Revwalk, Signature, Sort, Status, StatusOptions, Time};
 use log::{error, info, trace, warn};
 use regex::Regex;
+use serde::de::{self, Deserializer, MapAccess, Visitor};
 use serde::Deserialize;
 use std::cell::RefCell;
 use std::cmp::{min, Ord};
@@ -461,30 +462,30 @@ pub struct CommitInfoBuf {
   id: String,
   summary: String,
   message: String,
-  kind: String,
   files: Vec<String>
 }
 
 impl CommitInfoBuf {
-  pub fn new(id: String, kind: String, summary: String, message: String, files: Vec<String>) -> CommitInfoBuf {
-    CommitInfoBuf { id, summary, message, kind, files }
+  pub fn new(id: String, summary: String, message: String, files: Vec<String>) -> CommitInfoBuf {
+    CommitInfoBuf { id, summary, message, files }
   }
 
-  pub fn guess(id: String) -> CommitInfoBuf { CommitInfoBuf::new(id, "-".into(), "-".into(), "".into(), Vec::new()) }
+  pub fn guess(id: String) -> CommitInfoBuf { CommitInfoBuf::new(id, "-".into(), "".into(), Vec::new()) }
 
   pub fn extract<'a>(repo: &'a Repository, commit: &Commit<'a>) -> Result<CommitInfoBuf> {
     let id = commit.id().to_string();
     let summary = commit.summary().unwrap_or("-").to_string();
     let message = commit.message().unwrap_or("-").to_string();
-    let kind = extract_kind(&message);
     let files = files_from_commit(repo, commit)?.collect();
-    Ok(CommitInfoBuf::new(id, kind, summary, message, files))
+    Ok(CommitInfoBuf::new(id, summary, message, files))
   }
 
   pub fn id(&self) -> &str { &self.id }
   pub fn summary(&self) -> &str { &self.summary }
   pub fn message(&self) -> &str { &self.message }
-  pub fn kind(&self) -> &str { &self.kind }
+  pub fn kind(&self, format: &CommitFormat) -> String { format.kind(&self.message) }
+  pub fn scope(&self, format: &CommitFormat) -> Option<String> { format.scope(&self.message) }
+  pub fn footers(&self) -> Vec<Footer> { extract_footers(&self.message) }
   pub fn files(&self) -> &[String] { &self.files }
 }
 
@@ -499,13 +500,12 @@ impl<'a> CommitInfo<'a> {
   pub fn id(&self) -> String { self.commit.id().to_string() }
   pub fn summary(&self) -> &str { self.commit.summary().unwrap_or("-") }
   pub fn message(&self) -> &str { self.commit.message().unwrap_or("-") }
-  pub fn kind(&self) -> String { extract_kind(self.message()) }
+  pub fn kind(&self, format: &CommitFormat) -> String { format.kind(self.message()) }
   pub fn files(&self) -> Result<impl Iterator<Item = String> + 'a> { files_from_commit(&self.repo, &self.commit) }
 
   pub fn buffer(self) -> Result<CommitInfoBuf> {
     Ok(CommitInfoBuf::new(
       self.id(),
-      self.kind(),
       self.summary().to_string(),
       self.message().to_string(),
       self.files()?.collect()
@@ -921,14 +921,175 @@ fn fast_forward(repo: &Repository, rfrnc: &mut Reference, rc: &AnnotatedCommit)
   Ok(())
 }
 
+/// The format of commit messages, which determines how the "kind" (and scope) of a commit is found.
+#[derive(Clone, Debug)]
+pub enum CommitFormat {
+  /// Conventional commits, as in `feat(api)!: add endpoint`.
+  Conventional,
+  /// Gitmoji, as in `:sparkles: (api) add endpoint` or `✨ add endpoint`.
+  Gitmoji,
+  /// Jira-style prefixes, as in `[FEAT] API-123 add endpoint`; the Jira project key is the scope.
+  Jira,
+  /// A custom pattern, with named `kind`, `scope`, and `breaking` captures.
+  Custom(Regex)
+}
+
+impl CommitFormat {
+  /// Finds the kind of a commit from its message.
+  ///
+  /// The kind can be one of the special characters "-" (no kind found) or "!" (a breaking change, including any
+  /// commit with a "BREAKING CHANGE:" or "BREAKING-CHANGE:" starting footer).
+  pub fn kind(&self, message: &str) -> String {
+    if has_breaking_footer(message) {
+      return "!".into();
+    }
+
+    match self {
+      CommitFormat::Conventional => extract_kind(message),
+      CommitFormat::Gitmoji => extract_gitmoji(message).map(|(kind, _)| kind).unwrap_or_else(|| "-".into()),
+      CommitFormat::Jira => extract_jira(message).map(|(kind, _)| kind).unwrap_or_else(|| "-".into()),
+      CommitFormat::Custom(pattern) => match pattern.captures(message) {
+        Some(caps) if caps.name("breaking").map(|b| !b.as_str().is_empty()).unwrap_or(false) => "!".into(),
+        Some(caps) => caps
+          .name("kind")
+          .map(|k| k.as_str().trim().to_lowercase())
+          .filter(|k| !k.is_empty())
+          .unwrap_or_else(|| "-".into()),
+        None => "-".into()
+      }
+    }
+  }
+
+  /// Finds the scope of a commit from its message.
+  pub fn scope(&self, message: &str) -> Option<String> {
+    match self {
+      CommitFormat::Conventional => extract_scope(message),
+      CommitFormat::Gitmoji => extract_gitmoji(message).and_then(|(_, scope)| scope),
+      CommitFormat::Jira => extract_jira(message).and_then(|(_, scope)| scope),
+      CommitFormat::Custom(pattern) => pattern
+        .captures(message)
+        .and_then(|caps| caps.name("scope").map(|s| s.as_str().trim().to_lowercase()))
+        .filter(|s| !s.is_empty())
+    }
+  }
+}
+
+impl<'de> Deserialize<'de> for CommitFormat {
+  fn deserialize<D: Deserializer<'de>>(desr: D) -> std::result::Result<CommitFormat, D::Error> {
+    struct FormatVisitor;
+
+    impl<'de> Visitor<'de> for FormatVisitor {
+      type Value = CommitFormat;
+
+      fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a commit format name, or a custom pattern")
+      }
+
+      fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<CommitFormat, E> {
+        match v {
+          "conventional" => Ok(CommitFormat::Conventional),
+          "gitmoji" => Ok(CommitFormat::Gitmoji),
+          "jira" => Ok(CommitFormat::Jira),
+          other => Err(de::Error::custom(format!("Unknown commit format \"{}\".", other)))
+        }
+      }
+
+      fn visit_map<M: MapAccess<'de>>(self, mut map: M) -> std::result::Result<CommitFormat, M::Error> {
+        let mut pattern: Option<String> = None;
+        while let Some(key) = map.next_key::<String>()? {
+          match key.as_str() {
+            "pattern" => pattern = Some(map.next_value()?),
+            other => return Err(de::Error::custom(format!("Unrecognized commit format key \"{}\".", other)))
+          }
+        }
+
+        let pattern = pattern.ok_or_else(|| de::Error::missing_field("pattern"))?;
+        let pattern = Regex::new(&pattern).map_err(|e| de::Error::custom(format!("Bad commit pattern: {}", e)))?;
+        if !pattern.capture_names().any(|n| n == Some("kind")) {
+          return Err(de::Error::custom("Commit pattern has no \"kind\" capture."));
+        }
+        Ok(CommitFormat::Custom(pattern))
+      }
+    }
+
+    desr.deserialize_any(FormatVisitor)
+  }
+}
+
+/// Gitmoji codes and emoji, with the conventional commit type that each represents.
+const GITMOJIS: &[(&str, &str, &str)] = &[
+  ("boom", "\u{1f4a5}", "!"),
+  ("sparkles", "\u{2728}", "feat"),
+  ("bug", "\u{1f41b}", "fix"),
+  ("ambulance", "\u{1f691}", "fix"),
+  ("adhesive_bandage", "\u{1fa79}", "fix"),
+  ("lock", "\u{1f512}", "fix"),
+  ("memo", "\u{1f4dd}", "docs"),
+  ("art", "\u{1f3a8}", "style"),
+  ("rotating_light", "\u{1f6a8}", "style"),
+  ("recycle", "\u{267b}", "refactor"),
+  ("fire", "\u{1f525}", "refactor"),
+  ("zap", "\u{26a1}", "perf"),
+  ("white_check_mark", "\u{2705}", "test"),
+  ("test_tube", "\u{1f9ea}", "test"),
+  ("construction_worker", "\u{1f477}", "ci"),
+  ("green_heart", "\u{1f49a}", "ci"),
+  ("package", "\u{1f4e6}", "build"),
+  ("arrow_up", "\u{2b06}", "build"),
+  ("arrow_down", "\u{2b07}", "build"),
+  ("heavy_plus_sign", "\u{2795}", "build"),
+  ("heavy_minus_sign", "\u{2796}", "build"),
+  ("wrench", "\u{1f527}", "chore"),
+  ("bookmark", "\u{1f516}", "chore"),
+  ("rewind", "\u{23ea}", "revert")
+];
+
+/// Finds the kind and scope of a gitmoji commit. A gitmoji code that isn't known is its own kind, as `tada` for
+/// `:tada: first commit`.
+fn extract_gitmoji(message: &str) -> Option<(String, Option<String>)> {
+  let header = message.lines().next()?.trim_start();
+  let (kind, rest) = if let Some(code) = header.strip_prefix(':') {
+    let end = code.find(':')?;
+    let (code, rest) = (&code[.. end], &code[end + 1 ..]);
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '+') {
+      return None;
+    }
+    let kind = GITMOJIS.iter().find(|(c, ..)| *c == code).map(|(.., k)| *k).unwrap_or(code);
+    (kind.to_lowercase(), rest)
+  } else {
+    let (_, emoji, kind) = GITMOJIS.iter().find(|(_, e, _)| header.starts_with(e))?;
+    (kind.to_string(), header[emoji.len() ..].trim_start_matches('\u{fe0f}'))
+  };
+
+  let rest = rest.trim_start();
+  let scope = rest
+    .strip_prefix('(')
+    .and_then(|r| r.find(')').map(|i| r[.. i].trim().to_lowercase()))
+    .filter(|s| !s.is_empty());
+  Some((kind, scope))
+}
+
+/// Finds the kind and scope of a Jira-style commit, as in `[FEAT] PROJ-123 summary` or `[FIX!] summary`.
+fn extract_jira(message: &str) -> Option<(String, Option<String>)> {
+  let jira_pattern =
+    Regex::new("^\\s*\\[([A-Za-z][A-Za-z0-9_-]*)(!?)\\]\\s*(?:([A-Za-z][A-Za-z0-9_]*)-[0-9]+)?").unwrap();
+  let caps = jira_pattern.captures(message.lines().next()?)?;
+  let kind = if caps[2].is_empty() { caps[1].to_lowercase() } else { "!".into() };
+  Some((kind, caps.get(3).map(|s| s.as_str().to_lowercase())))
+}
+
+fn has_breaking_footer(message: &str) -> bool {
+  let breaking_pattern =
+    Regex::new("^(?s).*?\\n\\n((BREAKING CHANGE|BREAKING-CHANGE):|.*\n(BREAKING CHANGE|BREAKING-CHANGE):)").unwrap();
+  breaking_pattern.is_match(message)
+}
+
 /// Finds a conventional commit "type" from a commit message.
 ///
 /// The type can be one of the special characters "-" (no type found) or "!" ("BREAKING CHANGE:" or
 /// "BREAKING-CHANGE:" starting footer, or "!" after type/scope)
 fn extract_kind(message: &str) -> String {
-  let breaking_pattern =
-    Regex::new("^(?s).*?\\n\\n((BREAKING CHANGE|BREAKING-CHANGE):|.*\n(BREAKING CHANGE|BREAKING-CHANGE):)").unwrap();
-  if breaking_pattern.is_match(message) {
+  if has_breaking_footer(message) {
     return "!".into();
   }
 
@@ -954,6 +1115,70 @@ fn extract_kind(message: &str) -> String {
   }
 }
 
+/// A commit message footer that overrides how the commit is planned for a single project.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Footer {
+  /// `Release-As: <project>=<version>`: release the project as exactly the given version.
+  ReleaseAs(String, String),
+  /// `Versio-Bump: <project>=<size>`: treat the commit as the given size for the project.
+  Bump(String, String),
+  /// `Versio-Skip: <project>`: ignore the commit for the project.
+  Skip(String)
+}
+
+/// Finds all versio footers ("Release-As:", "Versio-Bump:", or "Versio-Skip:") in a commit message.
+///
+/// Like "BREAKING CHANGE:", these footers are only found after the first paragraph of the message. Footers that
+/// can't be understood are ignored with a warning, since the commit history can't easily be changed.
+fn extract_footers(message: &str) -> Vec<Footer> {
+  let footer_pattern = Regex::new("(?i)^(Release-As|Versio-Bump|Versio-Skip)\\s*:\\s*(.*?)\\s*$").unwrap();
+  let body = match message.find("\n\n") {
+    Some(i) => &message[i + 2 ..],
+    None => return Vec::new()
+  };
+
+  body
+    .lines()
+    .filter_map(|line| footer_pattern.captures(line))
+    .filter_map(|caps| {
+      let token = caps[1].to_lowercase();
+      let value = &caps[2];
+      let pair = value.find('=').map(|i| (value[.. i].trim().to_string(), value[i + 1 ..].trim().to_string()));
+      match (token.as_str(), pair) {
+        ("versio-skip", None) if !value.is_empty() => Some(Footer::Skip(value.to_string())),
+        ("release-as", Some((proj, vers))) if !proj.is_empty() => Some(Footer::ReleaseAs(proj, vers)),
+        ("versio-bump", Some((proj, size))) if !proj.is_empty() => Some(Footer::Bump(proj, size.to_lowercase())),
+        _ => {
+          warn!("Ignoring malformed commit footer \"{}\".", &caps[0]);
+          None
+        }
+      }
+    })
+    .collect()
+}
+
+/// Finds the commit ID reverted by a commit, from the "This reverts commit <id>." line that `git revert` adds
+/// to its message.
+pub fn extract_reverted(message: &str) -> Option<String> {
+  let revert_pattern = Regex::new("(?m)^This reverts commit ([0-9a-fA-F]{7,40})\\b").unwrap();
+  revert_pattern.captures(message).map(|caps| caps[1].to_lowercase())
+}
+
+/// Finds a conventional commit "scope" (as in `feat(api): add endpoint`) from a commit message.
+///
+/// The scope is only found in a conventional `type(scope)!:` header, so that (say) a `Revert "feat(api): x"` header
+/// doesn't limit the commit to the `api` scope.
+fn extract_scope(message: &str) -> Option<String> {
+  let header_pattern = Regex::new("^\\s*[A-Za-z][\\w-]*\\s*!?\\s*\\(([^()]*)\\)\\s*!?\\s*:").unwrap();
+  let caps = header_pattern.captures(message.lines().next()?)?;
+  let scope = caps[1].trim();
+  if scope.is_empty() {
+    None
+  } else {
+    Some(scope.to_lowercase())
+  }
+}
+
 fn files_from_commit<'a>(repo: &'a Repository, commit: &Commit<'a>) -> Result<impl Iterator<Item = String> + 'a> {
   if commit.parents().len() == 1 {
     let parent = commit.parent(0)?;
@@ -1188,7 +1413,8 @@ pub fn do_push(repo: &Repository, remote_name: &str, specs: &[String]) -> Result
 
 #[cfg(test)]
 mod test {
-  use super::extract_kind;
+  use super::{extract_footers, extract_kind, extract_reverted, extract_scope, CommitFormat, Footer};
+  use regex::Regex;
 
   #[test]
   fn test_kind_simple() {
@@ -1254,4 +1480,89 @@ mod test {
   fn test_uncertain() {
     assert_eq!(&extract_kind("ENG-123: I forgot to conventinal commit"), "eng-123");
   }
+
+  #[test]
+  fn test_scope() {
+    assert_eq!(extract_scope("thing(Scope): this is thing"), Some("scope".to_string()));
+    assert_eq!(extract_scope("thing(scope)!: this is thing"), Some("scope".to_string()));
+    assert_eq!(extract_scope("thing!(scope): this is thing"), Some("scope".to_string()));
+  }
+
+  #[test]
+  fn test_gitmoji() {
+    let format = CommitFormat::Gitmoji;
+    assert_eq!(&format.kind(":sparkles: add thing"), "feat");
+    assert_eq!(&format.kind("\u{1f41b} fix thing"), "fix");
+    assert_eq!(&format.kind("\u{267b}\u{fe0f} (core) clean up"), "refactor");
+    assert_eq!(&format.kind(":boom: remove thing"), "!");
+    assert_eq!(&format.kind(":tada: first commit"), "tada");
+    assert_eq!(&format.kind("add thing"), "-");
+    assert_eq!(format.scope(":sparkles: (API) add thing"), Some("api".to_string()));
+    assert_eq!(format.scope("\u{267b}\u{fe0f} (core) clean up"), Some("core".to_string()));
+    assert_eq!(format.scope(":sparkles: add thing"), None);
+  }
+
+  #[test]
+  fn test_jira() {
+    let format = CommitFormat::Jira;
+    assert_eq!(&format.kind("[FEAT] PROJ-123 add thing"), "feat");
+    assert_eq!(&format.kind("[Fix!] PROJ-123 break thing"), "!");
+    assert_eq!(&format.kind("PROJ-123 add thing"), "-");
+    assert_eq!(format.scope("[FEAT] PROJ-123 add thing"), Some("proj".to_string()));
+    assert_eq!(format.scope("[FEAT] add thing"), None);
+  }
+
+  #[test]
+  fn test_custom_format() {
+    let pattern = Regex::new("^(?P<kind>[A-Z]+)(?P<breaking>!?)( in (?P<scope>\\w+))? -").unwrap();
+    let format = CommitFormat::Custom(pattern);
+    assert_eq!(&format.kind("ADD in core - a thing"), "add");
+    assert_eq!(&format.kind("ADD! - a thing"), "!");
+    assert_eq!(&format.kind("a thing"), "-");
+    assert_eq!(&format.kind("ADD - a thing\n\nBREAKING CHANGE: yup"), "!");
+    assert_eq!(format.scope("ADD in core - a thing"), Some("core".to_string()));
+    assert_eq!(format.scope("ADD - a thing"), None);
+  }
+
+  #[test]
+  fn test_footers() {
+    let msg = "feat: thing\n\nbody\n\nRelease-As: core=2.0.0\nversio-bump: docs = Minor\nVersio-Skip: cli\nOther: x";
+    assert_eq!(
+      extract_footers(msg),
+      vec![
+        Footer::ReleaseAs("core".into(), "2.0.0".into()),
+        Footer::Bump("docs".into(), "minor".into()),
+        Footer::Skip("cli".into())
+      ]
+    );
+  }
+
+  #[test]
+  fn test_footers_malformed() {
+    assert!(extract_footers("feat: Release-As: core=2.0.0").is_empty());
+    assert!(extract_footers("feat: thing\n\nRelease-As: 2.0.0\nVersio-Skip:\nVersio-Bump: =minor").is_empty());
+  }
+
+  #[test]
+  fn test_reverted() {
+    let msg = "Revert \"feat: x\"\n\nThis reverts commit 0123456789abcdef0123456789abcdef01234567.\n";
+    assert_eq!(extract_reverted(msg), Some("0123456789abcdef0123456789abcdef01234567".to_string()));
+    assert_eq!(extract_reverted("feat: x\n\nThis is not a revert."), None);
+  }
+
+  #[test]
+  fn test_scope_none() {
+    assert_eq!(extract_scope("thing: this is (not) thing"), None);
+    assert_eq!(extract_scope("thing(): this is thing"), None);
+    assert_eq!(extract_scope("not (conventional)\n\nfoot: 1"), None);
+    assert_eq!(extract_scope(""), None);
+  }
+
+  #[test]
+  fn test_scope_unconventional() {
+    assert_eq!(extract_scope("Revert \"feat(api): x\""), None);
+    assert_eq!(extract_scope("see (docs) note: x"), None);
+    assert_eq!(extract_scope("Merge branch 'fix(core)': x"), None);
+    assert_eq!(extract_scope("fix (core) : x"), Some("core".to_string()));
+  }
 }
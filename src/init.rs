@@ -1,9 +1,10 @@
 //! Simple implementation of the `init` command.
 
-use crate::config::CONFIG_FILENAME;
+use crate::config::{ConfigFile, CONFIG_FILENAME};
 use crate::errors::{Error, Result};
-use crate::mark::Mark;
-use crate::scan::{find_reg_data, JsonScanner, Scanner, TomlScanner, XmlScanner};
+use crate::mark::{Mark, NamedData};
+use crate::scan::{find_reg_data, GradleScanner, IniScanner, JsonScanner, Scanner, TomlScanner, XmlScanner,
+                  YamlScanner};
 use error_chain::bail;
 use ignore::WalkBuilder;
 use log::warn;
@@ -13,8 +14,9 @@ use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::Path;
 
-pub fn init(max_depth: u16) -> Result<()> {
-  if Path::new(CONFIG_FILENAME).exists() {
+pub fn init(max_depth: u16, merge: bool) -> Result<()> {
+  let exists = Path::new(CONFIG_FILENAME).exists();
+  if exists && !merge {
     bail!("Versio is already initialized.");
   }
 
@@ -23,6 +25,10 @@ pub fn init(max_depth: u16) -> Result<()> {
     .filter_map(|r| r.map_err(Error::from).and_then(|e| find_project(e.file_name(), e.path())).transpose())
     .collect::<Result<_>>()?;
 
+  if exists {
+    return merge_yaml(projs);
+  }
+
   if projs.is_empty() {
     println!("No projects found.");
   }
@@ -31,6 +37,119 @@ pub fn init(max_depth: u16) -> Result<()> {
   Ok(())
 }
 
+/// Add the found projects that aren't already configured to the end of the existing config's projects, leaving the
+/// rest of the file as it is.
+fn merge_yaml(projs: Vec<ProjSummary>) -> Result<()> {
+  let data = std::fs::read_to_string(CONFIG_FILENAME)?;
+  let merged = merge_projects(&data, projs, |root| Path::new(root).exists())?;
+  for vanished in &merged.vanished {
+    warn!("{}", vanished);
+  }
+
+  if merged.added == 0 {
+    println!("No new projects found.");
+    return Ok(());
+  }
+
+  std::fs::write(CONFIG_FILENAME, &merged.data)?;
+  println!("Added {} new project(s).", merged.added);
+  Ok(())
+}
+
+/// The config data with new projects merged in.
+struct Merged {
+  data: String,
+  added: usize,
+  vanished: Vec<String>
+}
+
+/// Merge the found projects into the config data. Existing projects are matched by their roots, and keep their IDs
+/// and tag prefixes; new projects are given IDs after the highest existing one, and tag prefixes that aren't taken.
+fn merge_projects(data: &str, projs: Vec<ProjSummary>, root_exists: impl Fn(&str) -> bool) -> Result<Merged> {
+  let config = ConfigFile::read(data)?;
+
+  let mut roots = HashSet::new();
+  let mut prefixes = HashSet::new();
+  let mut vanished = Vec::new();
+  for proj in config.projects() {
+    let root = normal_root(proj.root().map(|r| r.as_str()));
+    if !root_exists(root) {
+      vanished.push(format!("The root \"{}\" of project {} ({}) no longer exists.", root, proj.id(), proj.name()));
+    }
+    roots.insert(root.to_string());
+    prefixes.extend(proj.tag_prefix().clone());
+  }
+
+  let news: Vec<_> = projs.into_iter().filter(|p| !roots.contains(normal_root(p.root()))).collect();
+  if news.is_empty() {
+    return Ok(Merged { data: data.to_string(), added: 0, vanished });
+  }
+
+  let next_id = config.projects().iter().map(|p| p.id().id()).max().unwrap_or(0) + 1;
+  let total = config.projects().len() + news.len();
+  let entries: String = news
+    .iter()
+    .enumerate()
+    .map(|(i, proj)| format!("\n{}", project_yaml(proj, next_id + i as u32, proj.tag_prefix(total, &mut prefixes))))
+    .collect();
+
+  Ok(Merged { data: insert_projects(data, &entries)?, added: news.len(), vanished })
+}
+
+fn normal_root(root: Option<&str>) -> &str {
+  let root = root.map(|r| r.trim_end_matches('/')).unwrap_or(".");
+  let root = root.strip_prefix("./").unwrap_or(root);
+  if root.is_empty() {
+    "."
+  } else {
+    root
+  }
+}
+
+/// Insert the project entries after the last item of the top-level `projects` list, re-indented to match that list.
+fn insert_projects(data: &str, entries: &str) -> Result<String> {
+  let lines: Vec<_> = data.split_inclusive('\n').collect();
+  let start = match lines.iter().position(|l| l.starts_with("projects:")) {
+    Some(start) => start,
+    None => {
+      let sep = if data.is_empty() || data.ends_with('\n') { "" } else { "\n" };
+      return Ok(format!("{}{}\nprojects:{}", data, sep, entries));
+    }
+  };
+
+  let rest = lines[start]["projects:".len() ..].trim();
+  if !rest.is_empty() && !rest.starts_with('#') {
+    bail!("Can't merge into the \"projects\" list of {}: it's not a block list.", CONFIG_FILENAME);
+  }
+
+  // The list continues through indented, blank, and comment lines, and items that aren't indented.
+  let in_list = |l: &str| l.trim().is_empty() || l.starts_with([' ', '\t', '#', '-']);
+  let end = start + 1 + lines[start + 1 ..].iter().take_while(|l| in_list(l)).count();
+  let last = (start + 1 .. end).rev().find(|i| !lines[*i].trim().is_empty() && !lines[*i].starts_with('#'));
+  let at = last.map(|i| i + 1).unwrap_or(start + 1);
+
+  let indent = lines[start + 1 .. end]
+    .iter()
+    .find(|l| l.trim_start().starts_with('-'))
+    .map(|l| &l[.. l.len() - l.trim_start().len()])
+    .unwrap_or("  ");
+  let entries: String = entries
+    .split_inclusive('\n')
+    .map(|l| match l.strip_prefix("  ") {
+      Some(l) => format!("{}{}", indent, l),
+      None => l.to_string()
+    })
+    .collect();
+
+  let mut result: String = lines[.. at].concat();
+  if !result.ends_with('\n') {
+    result.push('\n');
+  }
+  result.push_str(&entries);
+  result.push_str(&lines[at ..].concat());
+  Ok(result)
+}
+
 fn find_project(name: &OsStr, file: &Path) -> Result<Option<ProjSummary>> {
   let fname = match name.to_str() {
     Some(n) => n,
@@ -67,6 +186,49 @@ fn find_project(name: &OsStr, file: &Path) -> Result<Option<ProjSummary>> {
     return Ok(Some(ProjSummary::new_file(name, dir, "pom.xml", "xml", "project.version", &["mvn"])));
   }
 
+  if let Some(ps) = add_gradle(fname, file)? {
+    return Ok(Some(ps));
+  }
+
+  if let Some(ps) = add_pyproject(fname, file)? {
+    return Ok(Some(ps));
+  }
+
+  if let Some(ps) = add_composer(fname, file)? {
+    return Ok(Some(ps));
+  }
+
+  if fname == "Chart.yaml" {
+    let name = extract_name(file, |d| YamlScanner::new("name").find(&d))?;
+    let dir = file.parent().unwrap().to_string_lossy();
+    return Ok(Some(ProjSummary::new_file(name, dir, "Chart.yaml", "yaml", "version", &["helm"])));
+  }
+
+  if fname == "pubspec.yaml" {
+    let name = extract_name(file, |d| YamlScanner::new("name").find(&d))?;
+    let dir = file.parent().unwrap().to_string_lossy();
+    return Ok(Some(ProjSummary::new_file(name, dir, "pubspec.yaml", "yaml", "version", &["pub"])));
+  }
+
+  if let Some(ps) = add_dotnet(fname, file)? {
+    return Ok(Some(ps));
+  }
+
+  if let Some(ps) = add_mix(fname, file)? {
+    return Ok(Some(ps));
+  }
+
+  if fname == "Package.swift" {
+    // Swift packages are versioned only by their tags.
+    let dir = file.parent().unwrap();
+    let name = extract_name(file, |d| find_reg_data(&d, r#"name *: *"([^"]*)""#)).unwrap_or_else(|_| dir_name(dir));
+    return Ok(Some(ProjSummary::new_tags(name, dir.to_string_lossy(), false, &["swift"])));
+  }
+
+  if let Some(ps) = add_cmake(fname, file)? {
+    return Ok(Some(ps));
+  }
+
   if fname == "setup.py" {
     let name_reg = r#"name *= *['"]([^'"]*)['"]"#;
     let version_reg = r#"version *= *['"](\d+\.\d+\.\d+)['"]"#;
@@ -135,8 +297,165 @@ fn add_gemspec(fname: &str, file: &Path) -> Result<Option<ProjSummary>> {
   Ok(None)
 }
 
+fn add_gradle(fname: &str, file: &Path) -> Result<Option<ProjSummary>> {
+  if fname != "build.gradle" && fname != "build.gradle.kts" {
+    return Ok(None);
+  }
+
+  let dir = file.parent().unwrap();
+  let dirn = dir.to_string_lossy();
+
+  // The project name is set in the settings file, if there is one; otherwise, it's the name of the directory.
+  let settings = ["settings.gradle", "settings.gradle.kts"].iter().map(|s| dir.join(s)).find(|s| s.exists());
+  let name = settings
+    .and_then(|s| extract_name(&s, |d| GradleScanner::new("rootProject.name").find(&d)).ok())
+    .unwrap_or_else(|| dir_name(dir));
+
+  if extract_name(file, |d| GradleScanner::new("version").find(&d)).is_ok() {
+    return Ok(Some(ProjSummary::new_file(name, dirn, fname, "gradle", "version", &["gradle"])));
+  }
+
+  // Many projects keep the version in the properties file instead.
+  let props = dir.join("gradle.properties");
+  if props.exists() && extract_name(&props, |d| IniScanner::new("version").find(&d)).is_ok() {
+    return Ok(Some(ProjSummary::new_file(name, dirn, "gradle.properties", "ini", "version", &["gradle"])));
+  }
+
+  warn!("Couldn't find version in \"{}\". Please edit the .versio.yaml file.", file.to_string_lossy());
+  Ok(Some(ProjSummary::new_file(name, dirn, "EDIT_ME", "pattern", "EDIT_ME", &["gradle"])))
+}
+
+fn add_pyproject(fname: &str, file: &Path) -> Result<Option<ProjSummary>> {
+  if fname != "pyproject.toml" {
+    return Ok(None);
+  }
+
+  let dir = file.parent().unwrap();
+  let dirn = dir.to_string_lossy();
+  let find = |key: &str| extract_name(file, |d| TomlScanner::new(key).find(&d)).ok();
+
+  // PEP 621 metadata is under `project`, but Poetry (before 2.0) keeps its own under `tool.poetry`.
+  let (name, version, labels) = if let Some(name) = find("project.name") {
+    (name, "project.version", ["pip"])
+  } else if let Some(name) = find("tool.poetry.name") {
+    (name, "tool.poetry.version", ["poetry"])
+  } else {
+    return Ok(None);
+  };
+
+  let mut proj = if find(version).is_some() {
+    ProjSummary::new_file(name, dirn, fname, "toml", version, &labels)
+  } else {
+    warn!("Couldn't find version in \"{}\". Please edit the .versio.yaml file.", file.to_string_lossy());
+    ProjSummary::new_file(name, dirn, "EDIT_ME", "pattern", "EDIT_ME", &labels)
+  };
+
+  // A uv lockfile records the project's own version, so it has to be relocked.
+  if dir.join("uv.lock").exists() {
+    proj.hook("post_write", "uv lock");
+  }
+  Ok(Some(proj))
+}
+
+fn add_composer(fname: &str, file: &Path) -> Result<Option<ProjSummary>> {
+  if fname != "composer.json" {
+    return Ok(None);
+  }
+
+  let name = extract_name(file, |d| JsonScanner::new("name").find(&d))?;
+  let dir = file.parent().unwrap();
+  let dirn = dir.to_string_lossy();
+
+  // Composer packages usually take their versions from tags, unless the version is listed.
+  let mut proj = if extract_name(file, |d| JsonScanner::new("version").find(&d)).is_ok() {
+    ProjSummary::new_file(name, dirn, fname, "json", "version", &["composer"])
+  } else {
+    ProjSummary::new_tags(name, dirn, false, &["composer"])
+  };
+
+  // The lockfile has a hash of composer.json, which is outdated when the version is written.
+  if dir.join("composer.lock").exists() {
+    proj.hook("post_write", "composer update --lock");
+  }
+  Ok(Some(proj))
+}
+
+fn add_dotnet(fname: &str, file: &Path) -> Result<Option<ProjSummary>> {
+  let dir = file.parent().unwrap();
+  let name = if fname == "Directory.Build.props" {
+    dir_name(dir)
+  } else if let Some(stem) = fname.strip_suffix(".csproj") {
+    stem.to_string()
+  } else {
+    return Ok(None);
+  };
+
+  // Projects without a version might inherit it from a `Directory.Build.props` file, so are only found there.
+  let version_reg = r"<Version>\s*(\d+\.\d+\.\d+[^<\s]*)\s*</Version>";
+  if extract_name(file, |d| find_reg_data(&d, version_reg)).is_err() {
+    return Ok(None);
+  }
+
+  let dirn = dir.to_string_lossy();
+  Ok(Some(ProjSummary::new_file(name, dirn, fname, "pattern", version_reg, &["dotnet"])))
+}
+
+fn add_mix(fname: &str, file: &Path) -> Result<Option<ProjSummary>> {
+  if fname != "mix.exs" {
+    return Ok(None);
+  }
+
+  let dir = file.parent().unwrap();
+  let dirn = dir.to_string_lossy();
+  let name = extract_name(file, |d| find_reg_data(&d, r"app: *:(\w+)")).unwrap_or_else(|_| dir_name(dir));
+
+  // The version is often kept in a module attribute, which is then used in the project keywords.
+  for version_reg in [r#"@version +"(\d+\.\d+\.\d+[^"]*)""#, r#"version: *"(\d+\.\d+\.\d+[^"]*)""#] {
+    if extract_name(file, |d| find_reg_data(&d, version_reg)).is_ok() {
+      return Ok(Some(ProjSummary::new_file(name, dirn, fname, "pattern", version_reg, &["mix"])));
+    }
+  }
+
+  warn!("Couldn't find version in \"{}\". Please edit the .versio.yaml file.", file.to_string_lossy());
+  Ok(Some(ProjSummary::new_file(name, dirn, "EDIT_ME", "pattern", "EDIT_ME", &["mix"])))
+}
+
+fn add_cmake(fname: &str, file: &Path) -> Result<Option<ProjSummary>> {
+  if fname != "CMakeLists.txt" {
+    return Ok(None);
+  }
+
+  // Like go.mod, only the top-level list file of a tree describes a project.
+  let dir = file.parent().unwrap();
+  if dir.parent().map(|p| p.join("CMakeLists.txt").exists()).unwrap_or(false) {
+    return Ok(None);
+  }
+
+  let any_reg = r"(?i)project *\([^)]*\bVERSION\s+([\d.]+)";
+  let any_vers = match extract_name(file, |d| find_reg_data(&d, any_reg)) {
+    Ok(vers) => vers,
+    Err(_) => return Ok(None)
+  };
+
+  let name = extract_name(file, |d| find_reg_data(&d, r"(?i)project *\(\s*([\w-]+)")).unwrap_or_else(|_| dir_name(dir));
+  let dirn = dir.to_string_lossy();
+  let version_reg = r"(?i)project *\([^)]*\bVERSION\s+(\d+\.\d+\.\d+)[\s)]";
+  if extract_name(file, |d| find_reg_data(&d, version_reg)).is_ok() {
+    Ok(Some(ProjSummary::new_file(name, dirn, fname, "pattern", version_reg, &["cmake"])))
+  } else {
+    warn!("Version \"{}\" in \"{}\" isn't major.minor.patch. Please edit the .versio.yaml file.", any_vers, fname);
+    Ok(Some(ProjSummary::new_file(name, dirn, "EDIT_ME", "pattern", "EDIT_ME", &["cmake"])))
+  }
+}
+
 fn extract_name<F: FnOnce(String) -> Result<Mark>>(file: &Path, find: F) -> Result<String> {
-  std::fs::read_to_string(file).map_err(|e| e.into()).and_then(find).map(|mark| mark.value().to_string())
+  NamedData::read(file).map(String::from).and_then(find).map(|mark| mark.value().to_string())
+}
+
+/// The name of the directory itself, or a placeholder if it doesn't have one.
+fn dir_name(dir: &Path) -> String {
+  let name = dir.canonicalize().ok().and_then(|d| d.file_name()?.to_str().map(|n| n.to_string()));
+  name.unwrap_or_else(|| "project".into())
 }
 
 fn write_yaml(projs: &[ProjSummary]) -> Result<()> {
@@ -156,37 +475,7 @@ fn generate_yaml(projs: &[ProjSummary]) -> String {
 
   let mut prefixes = HashSet::new();
   for (id, proj) in projs.iter().enumerate() {
-    yaml.push_str(&format!("  - name: \"{}\"\n", proj.name()));
-    if let Some(root) = proj.root() {
-      yaml.push_str(&format!("    root: \"{}\"\n", root));
-    }
-    yaml.push_str(&format!("    id: {}\n", id + 1));
-    yaml.push_str(&format!("    tag_prefix: \"{}\"\n", proj.tag_prefix(projs.len(), &mut prefixes)));
-    if !proj.labels().is_empty() {
-      if proj.labels().len() == 1 {
-        yaml.push_str(&format!("    labels: {}\n", &proj.labels()[0]));
-      } else {
-        yaml.push_str("    labels:\n");
-        for l in proj.labels() {
-          yaml.push_str(&format!("      - {}\n", l));
-        }
-      }
-    }
-    yaml.push_str("    version:\n");
-    proj.append_version(&mut yaml);
-
-    if !proj.hooks().is_empty() {
-      let mut hooks: Vec<_> = proj.hooks().iter().collect();
-      hooks.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
-      yaml.push_str("    hooks:\n");
-      for (k, v) in hooks {
-        yaml.push_str(&format!("      {}: '{}'\n", k, yaml_escape_single(v)));
-      }
-    }
-
-    if proj.subs() {
-      yaml.push_str("    subs: {}\n");
-    }
+    yaml.push_str(&project_yaml(proj, id as u32 + 1, proj.tag_prefix(projs.len(), &mut prefixes)));
     yaml.push('\n');
   }
 
@@ -197,6 +486,43 @@ fn generate_yaml(projs: &[ProjSummary]) -> String {
   yaml
 }
 
+fn project_yaml(proj: &ProjSummary, id: u32, tag_prefix: String) -> String {
+  let mut yaml = String::new();
+  yaml.push_str(&format!("  - name: \"{}\"\n", proj.name()));
+  if let Some(root) = proj.root() {
+    yaml.push_str(&format!("    root: \"{}\"\n", root));
+  }
+  yaml.push_str(&format!("    id: {}\n", id));
+  yaml.push_str(&format!("    tag_prefix: \"{}\"\n", tag_prefix));
+  if !proj.labels().is_empty() {
+    if proj.labels().len() == 1 {
+      yaml.push_str(&format!("    labels: {}\n", &proj.labels()[0]));
+    } else {
+      yaml.push_str("    labels:\n");
+      for l in proj.labels() {
+        yaml.push_str(&format!("      - {}\n", l));
+      }
+    }
+  }
+  yaml.push_str("    version:\n");
+  proj.append_version(&mut yaml);
+
+  if !proj.hooks().is_empty() {
+    let mut hooks: Vec<_> = proj.hooks().iter().collect();
+    hooks.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+    yaml.push_str("    hooks:\n");
+    for (k, v) in hooks {
+      yaml.push_str(&format!("      {}: '{}'\n", k, yaml_escape_single(v)));
+    }
+  }
+
+  if proj.subs() {
+    yaml.push_str("    subs: {}\n");
+  }
+
+  yaml
+}
+
 fn append_ignore() -> Result<()> {
   let mut file = OpenOptions::new().create(true).append(true).open(".gitignore")?;
   Ok(file.write_all(b"/.versio-paused\n")?)
@@ -339,3 +665,266 @@ fn tag_sanitize(name: &str) -> String {
 
   prefix
 }
+
+#[cfg(test)]
+mod test {
+  use super::{find_project, insert_projects, merge_projects, ProjSummary, VersionSummary};
+  use crate::scan::find_reg_data;
+  use std::fs;
+  use std::path::PathBuf;
+
+  /// Write the files to a new directory, and detect a project from the first of them.
+  fn detect(dir: &str, files: &[(&str, &str)]) -> Option<ProjSummary> {
+    let root = test_dir(dir);
+    for (name, data) in files {
+      fs::write(root.join(name), data).unwrap();
+    }
+    let file = root.join(files[0].0);
+    find_project(file.file_name().unwrap(), &file).unwrap()
+  }
+
+  fn test_dir(dir: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("versio-init-{}", std::process::id())).join(dir);
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(&root).unwrap();
+    root
+  }
+
+  /// The name, labels, and version file, type, and parts of a detected project.
+  fn summary(proj: &ProjSummary) -> (&str, Vec<&str>, &str, &str, &str) {
+    let labels = proj.labels().iter().map(|l| l.as_str()).collect();
+    match &proj.version {
+      VersionSummary::File(f) => (proj.name(), labels, &f.file, &f.file_type, &f.parts),
+      VersionSummary::Tag(_) => (proj.name(), labels, "", "tags", "")
+    }
+  }
+
+  #[test]
+  fn test_detect_cmake() {
+    let data = "cmake_minimum_required(VERSION 3.14)\nproject(demo VERSION 1.2.3 LANGUAGES CXX)\n";
+    let proj = detect("cmake", &[("CMakeLists.txt", data)]).unwrap();
+    let (name, labels, file, file_type, parts) = summary(&proj);
+    assert_eq!(("demo", vec!["cmake"], "CMakeLists.txt", "pattern"), (name, labels, file, file_type));
+    assert_eq!("1.2.3", find_reg_data(data, parts).unwrap().value());
+
+    let data = "project(\n  demo-lib\n  VERSION 2.0.1\n)\n";
+    let proj = detect("cmake_multi", &[("CMakeLists.txt", data)]).unwrap();
+    assert_eq!("demo-lib", proj.name());
+    assert_eq!("2.0.1", find_reg_data(data, summary(&proj).4).unwrap().value());
+
+    for short in ["project(demo VERSION 1.2)\n", "project(demo VERSION 1.2.3.4)\n"] {
+      let proj = detect("cmake_short", &[("CMakeLists.txt", short)]).unwrap();
+      assert_eq!(("EDIT_ME", "EDIT_ME"), (summary(&proj).2, summary(&proj).4));
+    }
+
+    assert!(detect("cmake_none", &[("CMakeLists.txt", "project(demo LANGUAGES C)\n")]).is_none());
+  }
+
+  #[test]
+  fn test_detect_gradle() {
+    let settings = "rootProject.name = 'thing'\n";
+    let build = "plugins {\n  id 'java'\n}\n\nversion = '1.2.3'\n";
+    let proj = detect("gradle", &[("build.gradle", build), ("settings.gradle", settings)]).unwrap();
+    assert_eq!(("thing", vec!["gradle"], "build.gradle", "gradle", "version"), summary(&proj));
+
+    let build = "plugins {\n  java\n}\n";
+    let files = [("build.gradle.kts", build), ("gradle.properties", "group=com.acme\nversion=1.2.3\n")];
+    let proj = detect("gradle_props", &files).unwrap();
+    assert_eq!(("gradle_props", vec!["gradle"], "gradle.properties", "ini", "version"), summary(&proj));
+
+    let proj = detect("gradle_none", &[("build.gradle", build)]).unwrap();
+    assert_eq!(("EDIT_ME", "pattern", "EDIT_ME"), (summary(&proj).2, summary(&proj).3, summary(&proj).4));
+  }
+
+  #[test]
+  fn test_detect_pyproject() {
+    let pep621 = "[project]\nname = \"thing\"\nversion = \"1.2.3\"\n";
+    let proj = detect("pep621", &[("pyproject.toml", pep621), ("uv.lock", "")]).unwrap();
+    assert_eq!(("thing", vec!["pip"], "pyproject.toml", "toml", "project.version"), summary(&proj));
+    assert_eq!(Some("uv lock"), proj.hooks().get("post_write").map(|h| h.as_str()));
+
+    let poetry = "[tool.poetry]\nname = \"thing\"\nversion = \"1.2.3\"\n";
+    let proj = detect("poetry", &[("pyproject.toml", poetry)]).unwrap();
+    assert_eq!(("thing", vec!["poetry"], "pyproject.toml", "toml", "tool.poetry.version"), summary(&proj));
+    assert!(proj.hooks().is_empty());
+
+    let dynamic = "[project]\nname = \"thing\"\ndynamic = [\"version\"]\n";
+    let proj = detect("dynamic", &[("pyproject.toml", dynamic)]).unwrap();
+    assert_eq!(("EDIT_ME", "EDIT_ME"), (summary(&proj).2, summary(&proj).4));
+
+    assert!(detect("no_meta", &[("pyproject.toml", "[tool.black]\nline-length = 100\n")]).is_none());
+  }
+
+  #[test]
+  fn test_detect_composer() {
+    let data = r#"{ "name": "acme/thing", "version": "1.2.3" }"#;
+    let proj = detect("composer", &[("composer.json", data), ("composer.lock", "{}")]).unwrap();
+    assert_eq!(("acme/thing", vec!["composer"], "composer.json", "json", "version"), summary(&proj));
+    assert_eq!(Some("composer update --lock"), proj.hooks().get("post_write").map(|h| h.as_str()));
+
+    let proj = detect("composer_tags", &[("composer.json", r#"{ "name": "acme/thing" }"#)]).unwrap();
+    assert_eq!("tags", summary(&proj).3);
+  }
+
+  #[test]
+  fn test_detect_dotnet() {
+    let data = "<Project>\n  <PropertyGroup>\n    <Version>1.2.3-beta.1</Version>\n  </PropertyGroup>\n</Project>\n";
+    let proj = detect("dotnet", &[("Thing.Core.csproj", data)]).unwrap();
+    let (name, labels, file, file_type, parts) = summary(&proj);
+    assert_eq!(("Thing.Core", vec!["dotnet"], "Thing.Core.csproj", "pattern"), (name, labels, file, file_type));
+    assert_eq!("1.2.3-beta.1", find_reg_data(data, parts).unwrap().value());
+
+    let proj = detect("dotnet_props", &[("Directory.Build.props", data)]).unwrap();
+    assert_eq!("dotnet_props", proj.name());
+
+    let bare = r#"<Project>
+  <PropertyGroup>
+    <TargetFramework>net8.0</TargetFramework>
+  </PropertyGroup>
+</Project>"#;
+    assert!(detect("dotnet_bare", &[("Thing.csproj", bare)]).is_none());
+  }
+
+  #[test]
+  fn test_detect_mix() {
+    let data = r#"defmodule Thing.MixProject do
+  @version "1.2.3"
+
+  def project do
+    [app: :thing, version: @version]
+  end
+end"#;
+    let proj = detect("mix", &[("mix.exs", data)]).unwrap();
+    let (name, labels, file, file_type, parts) = summary(&proj);
+    assert_eq!(("thing", vec!["mix"], "mix.exs", "pattern"), (name, labels, file, file_type));
+    assert_eq!("1.2.3", find_reg_data(data, parts).unwrap().value());
+
+    let data = r#"defmodule Thing.MixProject do
+  def project do
+    [app: :thing, version: "0.4.0-rc.1"]
+  end
+end"#;
+    let proj = detect("mix_inline", &[("mix.exs", data)]).unwrap();
+    assert_eq!("0.4.0-rc.1", find_reg_data(data, summary(&proj).4).unwrap().value());
+  }
+
+  #[test]
+  fn test_detect_others() {
+    let proj = detect("helm", &[("Chart.yaml", "apiVersion: v2\nname: thing\nversion: 1.2.3\n")]).unwrap();
+    assert_eq!(("thing", vec!["helm"], "Chart.yaml", "yaml", "version"), summary(&proj));
+
+    let proj = detect("dart", &[("pubspec.yaml", "name: thing\nversion: 1.2.3+4\n")]).unwrap();
+    assert_eq!(("thing", vec!["pub"], "pubspec.yaml", "yaml", "version"), summary(&proj));
+
+    let swift = "// swift-tools-version:5.5\nlet package = Package(\n  name: \"Thing\",\n  products: []\n)\n";
+    let proj = detect("swift", &[("Package.swift", swift)]).unwrap();
+    assert_eq!(("Thing", vec!["swift"], "", "tags", ""), summary(&proj));
+  }
+
+  #[test]
+  fn test_insert_projects() {
+    let data = r#"# Our config.
+options:
+  prev_tag: "versio-prev"
+
+projects:
+# The main project.
+- name: "main"
+  id: 1
+  version:
+    file: "Cargo.toml"
+    toml: "package.version"
+
+# Retired.
+
+sizes:
+  fail: ["*"]
+"#;
+
+    let entries = "\n  - name: \"new\"\n    id: 2\n";
+    let expected = r#"# Our config.
+options:
+  prev_tag: "versio-prev"
+
+projects:
+# The main project.
+- name: "main"
+  id: 1
+  version:
+    file: "Cargo.toml"
+    toml: "package.version"
+
+- name: "new"
+  id: 2
+
+# Retired.
+
+sizes:
+  fail: ["*"]
+"#;
+
+    assert_eq!(expected, insert_projects(data, entries).unwrap());
+    assert_eq!("sizes: {}\n\nprojects:\n  - a\n", insert_projects("sizes: {}\n", "\n  - a\n").unwrap());
+    assert!(insert_projects("projects: []\n", entries).is_err());
+  }
+
+  #[test]
+  fn test_merge_projects() {
+    let data = r#"projects:
+  - name: "sub"
+    root: "./sub"
+    id: 3
+    tag_prefix: "sub"
+    version:
+      tags:
+        default: "0.0.0"
+  - name: "gone"
+    root: "gone/"
+    id: 7
+    tag_prefix: "lib"
+    version:
+      tags:
+        default: "0.0.0"
+"#;
+
+    let projs = vec![
+      ProjSummary::new_tags("sub", "sub/", false, &["npm"]),
+      ProjSummary::new_tags("lib", "./lib", false, &["npm"]),
+      ProjSummary::new_tags("other", "other", false, &["npm"])
+    ];
+    let merged = merge_projects(data, projs, |root| root != "gone").unwrap();
+
+    let expected = format!(
+      "{}{}",
+      data,
+      r#"
+  - name: "lib"
+    root: "lib"
+    id: 8
+    tag_prefix: "lib_2"
+    labels: npm
+    version:
+      tags:
+        default: "0.0.0"
+
+  - name: "other"
+    root: "other"
+    id: 9
+    tag_prefix: "other"
+    labels: npm
+    version:
+      tags:
+        default: "0.0.0"
+"#
+    );
+    assert_eq!(expected, merged.data);
+    assert_eq!(2, merged.added);
+    assert_eq!(vec!["The root \"gone\" of project 7 (gone) no longer exists.".to_string()], merged.vanished);
+
+    let projs = vec![ProjSummary::new_tags("sub", "./sub/", false, &["npm"])];
+    let merged = merge_projects(data, projs, |_| true).unwrap();
+    assert_eq!(data, merged.data);
+    assert_eq!(0, merged.added);
+    assert!(merged.vanished.is_empty());
+  }
+}
@@ -17,4 +17,6 @@ mod github;
 mod mark;
 mod mono;
 mod output;
+mod scheme;
 mod state;
+mod version;
@@ -1,10 +1,10 @@
 //! Management of reading and writing marks to files.
 
-use crate::errors::Result;
+use crate::errors::{Result, ResultExt as _};
 use crate::scan::parts::{deserialize_parts, Part};
-use crate::scan::{find_reg_data, scan_reg_data, JsonScanner, Scanner, TomlScanner, XmlScanner, YamlScanner};
+use crate::scan::{find_all_reg_data, find_reg_data, scan_all_reg_data, scan_reg_data, GradleScanner, HclScanner,
+                  IniScanner, JsonScanner, JsoncScanner, Scanner, TomlScanner, XmlScanner, YamlScanner};
 use error_chain::bail;
-use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::marker::PhantomData;
@@ -14,9 +14,13 @@ use std::path::{Path, PathBuf};
 #[serde(untagged)]
 pub enum Picker {
   Json(ScanningPicker<JsonScanner>),
+  Jsonc(ScanningPicker<JsoncScanner>),
   Yaml(ScanningPicker<YamlScanner>),
   Toml(ScanningPicker<TomlScanner>),
   Xml(ScanningPicker<XmlScanner>),
+  Ini(ScanningPicker<IniScanner>),
+  Hcl(ScanningPicker<HclScanner>),
+  Gradle(ScanningPicker<GradleScanner>),
   Line(LinePicker),
   File(FilePicker)
 }
@@ -26,9 +30,13 @@ impl Picker {
   pub fn picker_type(&self) -> &'static str {
     match self {
       Picker::Json(_) => "json",
+      Picker::Jsonc(_) => "jsonc",
       Picker::Yaml(_) => "yaml",
       Picker::Toml(_) => "toml",
       Picker::Xml(_) => "xml",
+      Picker::Ini(_) => "ini",
+      Picker::Hcl(_) => "hcl",
+      Picker::Gradle(_) => "gradle",
       Picker::Line(_) => "line",
       Picker::File(_) => "file"
     }
@@ -37,22 +45,60 @@ impl Picker {
   pub fn scan(&self, data: NamedData) -> Result<MarkedData> {
     match self {
       Picker::Json(p) => p.scan(data),
+      Picker::Jsonc(p) => p.scan(data),
       Picker::Yaml(p) => p.scan(data),
       Picker::Toml(p) => p.scan(data),
       Picker::Xml(p) => p.scan(data),
+      Picker::Ini(p) => p.scan(data),
+      Picker::Hcl(p) => p.scan(data),
+      Picker::Gradle(p) => p.scan(data),
       Picker::Line(p) => p.scan(data),
       Picker::File(p) => p.scan(data)
     }
   }
 
+  pub fn scan_all(&self, data: NamedData) -> Result<MarkedData> {
+    match self {
+      Picker::Json(p) => p.scan_all(data),
+      Picker::Jsonc(p) => p.scan_all(data),
+      Picker::Yaml(p) => p.scan_all(data),
+      Picker::Toml(p) => p.scan_all(data),
+      Picker::Xml(p) => p.scan_all(data),
+      Picker::Ini(p) => p.scan_all(data),
+      Picker::Hcl(p) => p.scan_all(data),
+      Picker::Gradle(p) => p.scan_all(data),
+      Picker::Line(p) => p.scan_all(data),
+      Picker::File(p) => p.scan(data)
+    }
+  }
+
   pub fn find(&self, data: &str) -> Result<Mark> {
     match self {
-      Picker::Json(p) => p.find_version(data),
-      Picker::Yaml(p) => p.find_version(data),
-      Picker::Toml(p) => p.find_version(data),
-      Picker::Xml(p) => p.find_version(data),
-      Picker::Line(p) => p.find_version(data),
-      Picker::File(p) => p.find_version(data)
+      Picker::Json(p) => p.find(data),
+      Picker::Jsonc(p) => p.find(data),
+      Picker::Yaml(p) => p.find(data),
+      Picker::Toml(p) => p.find(data),
+      Picker::Xml(p) => p.find(data),
+      Picker::Ini(p) => p.find(data),
+      Picker::Hcl(p) => p.find(data),
+      Picker::Gradle(p) => p.find(data),
+      Picker::Line(p) => p.find(data),
+      Picker::File(p) => p.find(data)
+    }
+  }
+
+  pub fn find_all(&self, data: &str) -> Result<Vec<Mark>> {
+    match self {
+      Picker::Json(p) => p.find_all(data),
+      Picker::Jsonc(p) => p.find_all(data),
+      Picker::Yaml(p) => p.find_all(data),
+      Picker::Toml(p) => p.find_all(data),
+      Picker::Xml(p) => p.find_all(data),
+      Picker::Ini(p) => p.find_all(data),
+      Picker::Hcl(p) => p.find_all(data),
+      Picker::Gradle(p) => p.find_all(data),
+      Picker::Line(p) => p.find_all(data),
+      Picker::File(p) => Ok(vec![p.find(data)?])
     }
   }
 }
@@ -74,8 +120,10 @@ impl<T: Scanner> fmt::Debug for ScanningPicker<T> {
 
 impl<T: Scanner> ScanningPicker<T> {
   pub fn new(parts: Vec<Part>) -> ScanningPicker<T> { ScanningPicker { parts, _scan: PhantomData } }
-  pub fn find_version(&self, data: &str) -> Result<Mark> { T::build(self.parts.clone()).find_version(data) }
+  pub fn find(&self, data: &str) -> Result<Mark> { T::build(self.parts.clone()).find(data) }
+  pub fn find_all(&self, data: &str) -> Result<Vec<Mark>> { T::build(self.parts.clone()).find_all(data) }
   pub fn scan(&self, data: NamedData) -> Result<MarkedData> { T::build(self.parts.clone()).scan(data) }
+  pub fn scan_all(&self, data: NamedData) -> Result<MarkedData> { T::build(self.parts.clone()).scan_all(data) }
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -86,14 +134,10 @@ pub struct LinePicker {
 impl LinePicker {
   pub fn new(pattern: String) -> LinePicker { LinePicker { pattern } }
   pub fn find(&self, data: &str) -> Result<Mark> { find_reg_data(data, &self.pattern) }
-
-  pub fn find_version(&self, data: &str) -> Result<Mark> {
-    let mark = self.find(data)?;
-    mark.validate_version()?;
-    Ok(mark)
-  }
+  pub fn find_all(&self, data: &str) -> Result<Vec<Mark>> { find_all_reg_data(data, &self.pattern) }
 
   pub fn scan(&self, data: NamedData) -> Result<MarkedData> { scan_reg_data(data, &self.pattern) }
+  pub fn scan_all(&self, data: NamedData) -> Result<MarkedData> { scan_all_reg_data(data, &self.pattern) }
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -105,12 +149,6 @@ impl FilePicker {
     Ok(Mark::new(value, 0))
   }
 
-  pub fn find_version(&self, data: &str) -> Result<Mark> {
-    let mark = self.find(data)?;
-    mark.validate_version()?;
-    Ok(mark)
-  }
-
   pub fn scan(&self, data: NamedData) -> Result<MarkedData> {
     let mark = self.find(data.data())?;
     Ok(data.mark(mark))
@@ -119,7 +157,8 @@ impl FilePicker {
 
 pub struct NamedData {
   writeable_path: PathBuf,
-  data: String
+  data: String,
+  encoding: Encoding
 }
 
 impl From<NamedData> for String {
@@ -127,26 +166,45 @@ impl From<NamedData> for String {
 }
 
 impl NamedData {
-  pub fn new(writeable_path: PathBuf, data: String) -> NamedData { NamedData { writeable_path, data } }
+  pub fn new(writeable_path: PathBuf, data: String, encoding: Encoding) -> NamedData {
+    NamedData { writeable_path, data, encoding }
+  }
+
+  /// Read a file, decoding it in whatever encoding it has.
+  pub fn read(path: &Path) -> Result<NamedData> {
+    let bytes = std::fs::read(path).chain_err(|| format!("Can't read file {}.", path.to_string_lossy()))?;
+    let (data, encoding) = Encoding::decode(&bytes).chain_err(|| format!("Can't decode {}.", path.to_string_lossy()))?;
+    Ok(NamedData::new(path.to_path_buf(), data, encoding))
+  }
+
   pub fn writeable_path(&self) -> &Path { &self.writeable_path }
   pub fn data(&self) -> &str { &self.data }
-  pub fn mark(self, mark: Mark) -> MarkedData { MarkedData::new(self.writeable_path, self.data, mark) }
+
+  pub fn mark(self, mark: Mark) -> MarkedData {
+    MarkedData::new(self.writeable_path, self.data, self.encoding, vec![mark])
+  }
+
+  pub fn mark_all(self, marks: Vec<Mark>) -> MarkedData {
+    MarkedData::new(self.writeable_path, self.data, self.encoding, marks)
+  }
 }
 
 pub struct MarkedData {
   writeable_path: PathBuf,
   data: String,
-  mark: Mark
+  encoding: Encoding,
+  marks: Vec<Mark>
 }
 
 impl MarkedData {
-  pub fn new(writeable_path: PathBuf, data: String, mark: Mark) -> MarkedData {
-    MarkedData { writeable_path, data, mark }
+  pub fn new(writeable_path: PathBuf, data: String, encoding: Encoding, mut marks: Vec<Mark>) -> MarkedData {
+    // Replace values from the end of the data first, so that earlier starts remain valid. Marks can be found more
+    // than once (for example, through YAML aliases), but should only be replaced once.
+    marks.sort_by_key(|m| std::cmp::Reverse(m.start()));
+    marks.dedup_by_key(|m| m.start());
+    MarkedData { writeable_path, data, encoding, marks }
   }
 
-  pub fn value(&self) -> &str { self.mark.value() }
-  pub fn start(&self) -> usize { self.mark.start() }
-
   pub fn write_new_value(&mut self, new_val: &str) -> Result<()> {
     self.set_value(new_val)?;
     self.write()?;
@@ -154,14 +212,78 @@ impl MarkedData {
   }
 
   fn set_value(&mut self, new_val: &str) -> Result<()> {
-    let st = self.start();
-    let ed = st + self.value().len();
-    self.data.replace_range(st .. ed, &new_val);
-    self.mark.set_value(new_val.to_string());
+    for mark in &mut self.marks {
+      let st = mark.start();
+      let ed = st + mark.value().len();
+      self.data.replace_range(st .. ed, &new_val);
+      mark.set_value(new_val.to_string());
+    }
     Ok(())
   }
 
-  fn write(&self) -> Result<()> { Ok(std::fs::write(&self.writeable_path, &self.data)?) }
+  fn write(&self) -> Result<()> { Ok(std::fs::write(&self.writeable_path, self.encoding.encode(&self.data))?) }
+}
+
+/// The encoding of a file, which is kept so that it can be written back exactly as it was read. Line endings need no
+/// special handling, since the data is never normalized: only the marked values are replaced.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+  Utf8,
+  Utf8Bom,
+  Utf16Le,
+  Utf16Be,
+  Utf16LeNoBom,
+  Utf16BeNoBom
+}
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+impl Encoding {
+  /// Decode the data, using its byte order mark (if any) to detect its encoding. The mark isn't part of the data.
+  ///
+  /// Data without a mark that has null bytes is taken to be UTF-16, since text in UTF-8 has none: its byte order is
+  /// given by whether most of the null bytes are at odd (little-endian) or even (big-endian) offsets.
+  pub fn decode(bytes: &[u8]) -> Result<(String, Encoding)> {
+    if let Some(rest) = bytes.strip_prefix(&UTF8_BOM) {
+      Ok((std::str::from_utf8(rest)?.to_string(), Encoding::Utf8Bom))
+    } else if let Some(rest) = bytes.strip_prefix(&UTF16_LE_BOM) {
+      Ok((decode_utf16(rest, u16::from_le_bytes)?, Encoding::Utf16Le))
+    } else if let Some(rest) = bytes.strip_prefix(&UTF16_BE_BOM) {
+      Ok((decode_utf16(rest, u16::from_be_bytes)?, Encoding::Utf16Be))
+    } else if bytes.contains(&0) {
+      let odd_nulls = bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+      let even_nulls = bytes.iter().step_by(2).filter(|b| **b == 0).count();
+      if odd_nulls >= even_nulls {
+        Ok((decode_utf16(bytes, u16::from_le_bytes)?, Encoding::Utf16LeNoBom))
+      } else {
+        Ok((decode_utf16(bytes, u16::from_be_bytes)?, Encoding::Utf16BeNoBom))
+      }
+    } else {
+      Ok((std::str::from_utf8(bytes)?.to_string(), Encoding::Utf8))
+    }
+  }
+
+  pub fn encode(&self, data: &str) -> Vec<u8> {
+    match self {
+      Encoding::Utf8 => data.as_bytes().to_vec(),
+      Encoding::Utf8Bom => UTF8_BOM.iter().copied().chain(data.bytes()).collect(),
+      Encoding::Utf16Le => UTF16_LE_BOM.iter().copied().chain(data.encode_utf16().flat_map(u16::to_le_bytes)).collect(),
+      Encoding::Utf16Be => UTF16_BE_BOM.iter().copied().chain(data.encode_utf16().flat_map(u16::to_be_bytes)).collect(),
+      Encoding::Utf16LeNoBom => data.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+      Encoding::Utf16BeNoBom => data.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+  }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Result<String> {
+  let chunks = bytes.chunks_exact(2);
+  if !chunks.remainder().is_empty() {
+    bail!("Odd number of bytes in UTF-16 data.");
+  }
+  let units = chunks.map(|c| unit([c[0], c[1]]));
+  char::decode_utf16(units).collect::<std::result::Result<_, _>>().map_err(|e| bad!("Bad UTF-16 data: {}", e))
 }
 
 #[derive(Debug)]
@@ -173,9 +295,12 @@ pub struct Mark {
 impl Mark {
   pub fn new(value: String, byte_start: usize) -> Mark { Mark { value, byte_start } }
 
+  /// Check that the value looks like a version number of any scheme: each project will check its own version
+  /// more strictly against its scheme.
   pub fn validate_version(&self) -> Result<()> {
-    let regex = Regex::new(r"\A\d+\.\d+\.\d+\z")?;
-    if !regex.is_match(&self.value) {
+    let plausible = self.value.starts_with(|c: char| c.is_ascii_digit())
+      && self.value.chars().all(|c| c.is_ascii_alphanumeric() || ".+-_!".contains(c));
+    if !plausible {
       bail!("Value \"{}\" is not a version.", self.value);
     }
 
@@ -211,7 +336,18 @@ impl CharMark {
 
 #[cfg(test)]
 mod test {
-  use super::find_reg_data;
+  use super::{find_all_reg_data, find_reg_data, Encoding, Mark, MarkedData};
+
+  #[test]
+  fn test_find_all_reg() {
+    let data = r#"
+Install with `npm i thing@1.2.3`,
+or `yarn add thing@1.2.3`."#;
+
+    let marks = find_all_reg_data(data, "thing@(\\d+\\.\\d+\\.\\d+)").unwrap();
+    let found: Vec<_> = marks.iter().map(|m| (m.value(), m.start())).collect();
+    assert_eq!(vec![("1.2.3", 27), ("1.2.3", 54)], found);
+  }
 
   #[test]
   fn test_find_reg() {
@@ -223,4 +359,62 @@ Current rev is "v1.2.3" because it is."#;
     assert_eq!("1.2.3", mark.value());
     assert_eq!(32, mark.start());
   }
+
+  #[test]
+  fn test_find_reg_named() {
+    let data = "Download thing-1.2.3-linux-amd64.tar.gz today.";
+
+    let mark = find_reg_data(data, "(thing)-(?P<version>[0-9.]+)-(linux)").unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(15, mark.start());
+  }
+
+  #[test]
+  fn test_set_values() {
+    let data = "a: &v 1.2.3\nb: 1.2.3\n";
+    let marks = vec![Mark::new("1.2.3".into(), 6), Mark::new("1.2.3".into(), 15), Mark::new("1.2.3".into(), 6)];
+    let mut marked = MarkedData::new("unused".into(), data.into(), Encoding::Utf8, marks);
+
+    marked.set_value("1.10.0").unwrap();
+    assert_eq!("a: &v 1.10.0\nb: 1.10.0\n", marked.data);
+  }
+
+  #[test]
+  fn test_encodings() {
+    let data = "version=1.2.3\r\nname=thing\r\n";
+    let encodings = [
+      Encoding::Utf8,
+      Encoding::Utf8Bom,
+      Encoding::Utf16Le,
+      Encoding::Utf16Be,
+      Encoding::Utf16LeNoBom,
+      Encoding::Utf16BeNoBom
+    ];
+    for encoding in encodings {
+      let bytes = encoding.encode(data);
+      assert_eq!((data.to_string(), encoding), Encoding::decode(&bytes).unwrap());
+
+      let mut marked = MarkedData::new("unused".into(), data.into(), encoding, vec![Mark::new("1.2.3".into(), 8)]);
+      marked.set_value("1.3.0").unwrap();
+      assert_eq!(encoding.encode("version=1.3.0\r\nname=thing\r\n"), encoding.encode(&marked.data));
+    }
+
+    assert_eq!(vec![0xFF, 0xFE, b'1', 0, b'\r', 0, b'\n', 0], Encoding::Utf16Le.encode("1\r\n"));
+    assert!(Encoding::decode(&[0xFF, 0xFE, b'1']).is_err());
+  }
+
+  #[test]
+  fn test_encodings_no_bom() {
+    let le = Encoding::decode(&[b'1', 0, b'.', 0, b'2', 0]).unwrap();
+    assert_eq!(("1.2".to_string(), Encoding::Utf16LeNoBom), le);
+    let be = Encoding::decode(&[0, b'1', 0, b'.', 0, b'2']).unwrap();
+    assert_eq!(("1.2".to_string(), Encoding::Utf16BeNoBom), be);
+
+    // Non-ASCII characters have no null bytes, but the ASCII around them decides the byte order.
+    let bytes = Encoding::Utf16LeNoBom.encode("v=\u{4e2d}\u{6587}1.2");
+    assert_eq!(("v=\u{4e2d}\u{6587}1.2".to_string(), Encoding::Utf16LeNoBom), Encoding::decode(&bytes).unwrap());
+
+    let err = Encoding::decode(&[b'1', 0, b'.']).unwrap_err();
+    assert_eq!("Odd number of bytes in UTF-16 data.", err.to_string());
+  }
 }
@@ -4,13 +4,15 @@ use crate::analyze::{analyze, Analysis};
 use crate::config::{Config, ConfigFile, Depends, FsConfig, Project, ProjectId, Size};
 use crate::either::{IterEither2 as E2, IterEither3 as E3};
 use crate::errors::Result;
-use crate::git::{Auth, CommitInfoBuf, FromTag, FromTagBuf, FullPr, GithubInfo, Repo};
+use crate::git::{extract_reverted, Auth, CommitFormat, CommitInfoBuf, Footer, FromTag, FromTagBuf, FullPr, GithubInfo,
+                 Repo};
 use crate::github::{changes, line_commits_head, Changes};
+use crate::scheme::VersionScheme;
 use crate::state::{CommitArgs, CurrentState, OldTags, PrevFiles, PrevTagMessage, StateRead, StateWrite};
 use crate::vcs::VcsState;
 use chrono::{DateTime, FixedOffset};
 use error_chain::bail;
-use log::trace;
+use log::{trace, warn};
 use serde::Deserialize;
 use std::cmp::{max, Ordering};
 use std::collections::{HashMap, HashSet, VecDeque};
@@ -73,6 +75,12 @@ impl Mono {
     }
   }
 
+  /// The pre-release channel configured for the current branch, if any.
+  pub fn branch_prerelease(&self) -> Option<&str> {
+    let branch_name = self.repo.branch_name().ok()?.as_ref()?;
+    self.current.branch_prerelease(branch_name)
+  }
+
   pub fn commit(&mut self, advance_prev: bool, pause: bool) -> Result<()> {
     self.next.commit(
       &self.repo,
@@ -139,6 +147,21 @@ impl Mono {
     self.do_project_write(id, move |p, n| p.write_changelog(n, changelog, new_vers))
   }
 
+  pub fn write_promoted_changelog(&mut self, id: &ProjectId, new_vers: &str) -> Result<Option<PathBuf>> {
+    self.do_project_write(id, move |p, n| p.write_promoted_changelog(n, new_vers))
+  }
+
+  /// Find the `(id, dependent)` pairs of all projects that depend on the given project.
+  pub fn dependents(&self, id: &ProjectId) -> Vec<(ProjectId, ProjectId)> {
+    self
+      .current
+      .projects()
+      .iter()
+      .filter(|p| p.depends().contains_key(id))
+      .map(|p| (id.clone(), p.id().clone()))
+      .collect()
+  }
+
   fn do_project_write<F, T>(&mut self, id: &ProjectId, f: F) -> Result<T>
   where
     F: FnOnce(&Project, &mut StateWrite) -> Result<T>
@@ -160,7 +183,7 @@ impl Mono {
 
     let mut vec = Vec::new();
     for pr in prs {
-      vec.push(pr_keyed_files(&self.repo, pr));
+      vec.push(pr_keyed_files(&self.repo, pr, self.current.file().commit_format().clone()));
     }
 
     Ok(vec.into_iter().flatten())
@@ -183,6 +206,9 @@ impl Mono {
       plan.finish_pr()?;
     }
 
+    // Commits reverted in the same release don't count, and neither do their reverts.
+    plan.cancel_reverts()?;
+
     // Some projects might depend on other projects.
     plan.handle_deps()?;
 
@@ -256,7 +282,9 @@ fn find_last_commits(current: &Config<CurrentState>, repo: &Repo) -> Result<Hash
   result
 }
 
-fn pr_keyed_files<'a>(repo: &'a Repo, pr: FullPr) -> impl Iterator<Item = Result<(String, String)>> + 'a {
+fn pr_keyed_files<'a>(
+  repo: &'a Repo, pr: FullPr, format: CommitFormat
+) -> impl Iterator<Item = Result<(String, String)>> + 'a {
   let head_oid = match pr.head_oid() {
     Some(oid) => *oid,
     None => return E3::C(empty())
@@ -271,7 +299,7 @@ fn pr_keyed_files<'a>(repo: &'a Repo, pr: FullPr) -> impl Iterator<Item = Result
           } else {
             match cmt.files() {
               Ok(files) => {
-                let kind = cmt.kind();
+                let kind = cmt.kind(&format);
                 Some(E2::A(files.map(move |f| Ok((kind.clone(), f)))))
               }
               Err(e) => Some(E2::B(once(Err(e))))
@@ -292,13 +320,15 @@ fn pr_keyed_files<'a>(repo: &'a Repo, pr: FullPr) -> impl Iterator<Item = Result
 pub struct Plan {
   incrs: HashMap<ProjectId, (Size, Changelog)>, // proj ID, incr size, changelog
   ineffective: Vec<LoggedPr>,                   // PRs that didn't apply to any project
-  chain_writes: Vec<(ProjectId, ProjectId)>
+  chain_writes: Vec<(ProjectId, ProjectId)>,
+  release_as: HashMap<ProjectId, String>        // proj ID, exact version from a "Release-As:" footer
 }
 
 impl Plan {
   pub fn incrs(&self) -> &HashMap<ProjectId, (Size, Changelog)> { &self.incrs }
   pub fn ineffective(&self) -> &[LoggedPr] { &self.ineffective }
   pub fn chain_writes(&self) -> &[(ProjectId, ProjectId)] { &self.chain_writes }
+  pub fn release_as(&self, id: &ProjectId) -> Option<&str> { self.release_as.get(id).map(|v| v.as_str()) }
 }
 
 pub struct Changelog {
@@ -322,6 +352,15 @@ impl Changelog {
   pub fn is_empty(&self) -> bool { self.entries.is_empty() }
 }
 
+impl ChangelogEntry {
+  fn pr(&self) -> Option<&LoggedPr> {
+    match self {
+      ChangelogEntry::Pr(pr, _) => Some(pr),
+      _ => None
+    }
+  }
+}
+
 pub struct LoggedPr {
   number: u32,
   title: String,
@@ -358,17 +397,18 @@ pub struct LoggedCommit {
   size: Size,
   applies: bool,
   duplicate: bool,
+  reverted: bool,
   url: Option<String>
 }
 
 impl LoggedCommit {
   pub fn new(oid: String, summary: String, message: String, size: Size, url: Option<String>) -> LoggedCommit {
-    LoggedCommit { oid, summary, message, size, applies: false, duplicate: false, url }
+    LoggedCommit { oid, summary, message, size, applies: false, duplicate: false, reverted: false, url }
   }
 
   pub fn applies(&self) -> bool { self.applies }
   pub fn duplicate(&self) -> bool { self.duplicate }
-  pub fn included(&self) -> bool { self.applies && !self.duplicate }
+  pub fn included(&self) -> bool { self.applies && !self.duplicate && !self.reverted }
   pub fn oid(&self) -> &str { &self.oid }
   pub fn summary(&self) -> &str { &self.summary }
   pub fn message(&self) -> &str { &self.message }
@@ -380,12 +420,15 @@ struct PlanBuilder<'s> {
   on_pr_sizes: HashMap<ProjectId, LoggedPr>,
   on_ineffective: Option<LoggedPr>,
   on_commit: Option<CommitInfoBuf>,
+  on_scoped: Option<HashSet<ProjectId>>,
   prev: Slicer<'s>,
   current: &'s ConfigFile,
   incrs: HashMap<ProjectId, (Size, Changelog)>, // proj ID, incr size, changelog
   ineffective: Vec<LoggedPr>,                   // PRs that didn't apply to any project
   github_info: Option<GithubInfo>,
-  chain_writes: Vec<(ProjectId, ProjectId)>
+  chain_writes: Vec<(ProjectId, ProjectId)>,
+  release_as_found: HashMap<ProjectId, Vec<(String, String)>>, // proj ID, (commit ID, version) from "Release-As:"
+  release_as: HashMap<ProjectId, String>
 }
 
 impl<'s> PlanBuilder<'s> {
@@ -396,12 +439,15 @@ impl<'s> PlanBuilder<'s> {
       on_pr_sizes: HashMap::new(),
       on_ineffective: None,
       on_commit: None,
+      on_scoped: None,
       prev,
       current,
       incrs: HashMap::new(),
       ineffective: Vec::new(),
       github_info,
-      chain_writes: Vec::new()
+      chain_writes: Vec::new(),
+      release_as_found: HashMap::new(),
+      release_as: HashMap::new()
     };
     Ok(builder)
   }
@@ -445,9 +491,23 @@ impl<'s> PlanBuilder<'s> {
 
   pub fn start_commit(&mut self, commit: CommitInfoBuf) -> Result<()> {
     let id = commit.id().to_string();
-    let kind = commit.kind().to_string();
+    let kind = commit.kind(self.current.commit_format());
     let summary = commit.summary().to_string();
     let msg = commit.message().to_string();
+    let footers = commit.footers();
+    for footer in &footers {
+      if !self.current.projects().iter().any(|p| footer_names(footer, p)) {
+        warn!("Ignoring footer {:?} for unknown project in commit {}.", footer, id);
+      }
+    }
+
+    // A scope claimed by some projects limits the commit to only those projects.
+    self.on_scoped = commit
+      .scope(self.current.commit_format())
+      .map(|scope| {
+        self.current.projects().iter().filter(|p| p.has_scope(&scope)).map(|p| p.id().clone()).collect::<HashSet<_>>()
+      })
+      .filter(|scoped| !scoped.is_empty());
     self.on_commit = Some(commit);
     self.prev.slice_to(FromTagBuf::new(id.clone(), false))?;
 
@@ -459,8 +519,40 @@ impl<'s> PlanBuilder<'s> {
 
     for (proj_id, logged_pr) in &mut self.on_pr_sizes {
       if let Some(cur_project) = self.current.get_project(proj_id) {
-        let size = cur_project.size(&self.current.sizes(), &kind)?;
-        logged_pr.commits.push(LoggedCommit::new(id.clone(), summary.clone(), msg.clone(), size, url.clone()));
+        let mut size = cur_project.size(&self.current.sizes(), &kind)?;
+        let mut forced = false;
+        let mut skipped = false;
+        for footer in footers.iter().filter(|f| footer_names(f, cur_project)) {
+          match footer {
+            Footer::Skip(_) => {
+              trace!("    skipping commit {} for {}.", id, proj_id);
+              skipped = true;
+              break;
+            }
+            Footer::Bump(_, bump) => match bump.parse() {
+              Ok(bump) => {
+                size = bump;
+                forced = true;
+              }
+              Err(_) => warn!("Ignoring unknown size \"{}\" in commit {}.", bump, id)
+            },
+            Footer::ReleaseAs(_, vers) => {
+              if let Err(e) = cur_project.scheme().validate(vers) {
+                warn!("Ignoring bad release version \"{}\" in commit {}: {}", vers, id, e);
+              } else {
+                forced = true;
+                self.release_as_found.entry(proj_id.clone()).or_default().push((id.clone(), vers.clone()));
+              }
+            }
+          }
+        }
+
+        if skipped {
+          continue;
+        }
+        let mut logged = LoggedCommit::new(id.clone(), summary.clone(), msg.clone(), size, url.clone());
+        logged.applies = forced;
+        logged_pr.commits.push(logged);
       }
     }
 
@@ -480,10 +572,15 @@ impl<'s> PlanBuilder<'s> {
     for prev_project in self.prev.file()?.projects() {
       if let Some(logged_pr) = self.on_pr_sizes.get_mut(&prev_project.id()) {
         trace!("      vs current project {}.", prev_project.id());
-        if prev_project.does_cover(path)? {
-          let LoggedCommit { applies, .. } = logged_pr.commits.iter_mut().find(|c| c.oid == commit_id).unwrap();
-          *applies = true;
-          trace!("        covered.");
+        if self.on_scoped.as_ref().map(|s| !s.contains(prev_project.id())).unwrap_or(false) {
+          trace!("        out of scope.");
+        } else if prev_project.does_cover(path)? {
+          if let Some(LoggedCommit { applies, .. }) = logged_pr.commits.iter_mut().find(|c| c.oid == commit_id) {
+            *applies = true;
+            trace!("        covered.");
+          } else {
+            trace!("        covered, but skipped.");
+          }
         } else {
           trace!("        not covered.");
         }
@@ -537,6 +634,70 @@ impl<'s> PlanBuilder<'s> {
     Ok(())
   }
 
+  /// Mark commits that are reverted by another commit in the plan, and the reverting commits themselves, so that
+  /// neither contributes to sizing or changelogs. This has to happen before dependencies are handled, since it can
+  /// shrink the size of a project.
+  pub fn cancel_reverts(&mut self) -> Result<()> {
+    for (size, changelog) in self.incrs.values_mut() {
+      let commits = || changelog.entries.iter().filter_map(|e| e.pr()).flat_map(|pr| pr.commits.iter());
+      let oids: Vec<&str> = commits().map(|c| c.oid.as_str()).collect();
+
+      let mut reverted_by: HashMap<String, Vec<String>> = HashMap::new();
+      for commit in commits() {
+        let target = extract_reverted(&commit.message).and_then(|t| oids.iter().find(|o| o.starts_with(&t)).copied());
+        if let Some(target) = target {
+          reverted_by.entry(target.to_string()).or_default().push(commit.oid.clone());
+        }
+      }
+      if reverted_by.is_empty() {
+        continue;
+      }
+
+      let mut effective = HashMap::new();
+      let mut cancelled = HashSet::new();
+      for (target, reverts) in &reverted_by {
+        if let Some(revert) = reverts.iter().find(|r| is_effective(r, &reverted_by, &mut effective)) {
+          trace!("Commit {} is reverted by {}.", target, revert);
+          cancelled.insert(target.clone());
+          cancelled.insert(revert.clone());
+        }
+      }
+
+      let mut new_size = Size::Empty;
+      for entry in &mut changelog.entries {
+        if let ChangelogEntry::Pr(pr, _) = entry {
+          for commit in &mut pr.commits {
+            commit.reverted = cancelled.contains(&commit.oid);
+            if commit.applies && !commit.reverted {
+              new_size = max(new_size, commit.size);
+            }
+          }
+        }
+      }
+      *size = new_size;
+    }
+
+    // Only the "Release-As:" footers of commits that are still in effect count.
+    for (proj_id, found) in &self.release_as_found {
+      let reverted: HashSet<&str> = match self.incrs.get(proj_id) {
+        Some((_, changelog)) => changelog
+          .entries
+          .iter()
+          .filter_map(|e| e.pr())
+          .flat_map(|pr| pr.commits.iter())
+          .filter(|c| c.reverted)
+          .map(|c| c.oid.as_str())
+          .collect(),
+        None => HashSet::new()
+      };
+      let scheme = self.current.get_project(proj_id).ok_or_else(|| bad!("No such project {}.", proj_id))?.scheme();
+      if let Some(vers) = highest_release_as(scheme, found, &reverted)? {
+        self.release_as.insert(proj_id.clone(), vers);
+      }
+    }
+    Ok(())
+  }
+
   pub fn sort_and_dedup(&mut self) -> Result<()> {
     for (.., changelog) in self.incrs.values_mut() {
       changelog.entries.sort_by(|entry1, entry2| match entry1 {
@@ -567,10 +728,48 @@ impl<'s> PlanBuilder<'s> {
   }
 
   pub fn build(self) -> Plan {
-    Plan { incrs: self.incrs, ineffective: self.ineffective, chain_writes: self.chain_writes }
+    Plan {
+      incrs: self.incrs,
+      ineffective: self.ineffective,
+      chain_writes: self.chain_writes,
+      release_as: self.release_as
+    }
   }
 }
 
+/// Check if a commit is in effect: that is, it isn't reverted by some other commit which is itself in effect. A
+/// commit can only be reverted by a later commit, so this always terminates.
+fn is_effective(oid: &str, reverted_by: &HashMap<String, Vec<String>>, memo: &mut HashMap<String, bool>) -> bool {
+  if let Some(effective) = memo.get(oid) {
+    return *effective;
+  }
+  let reverts = reverted_by.get(oid).map(|r| r.as_slice()).unwrap_or(&[]);
+  let effective = !reverts.iter().any(|r| is_effective(r, reverted_by, memo));
+  memo.insert(oid.to_string(), effective);
+  effective
+}
+
+/// Find the highest version of the "Release-As:" footers found for a project, ignoring those of reverted commits.
+fn highest_release_as(
+  scheme: &dyn VersionScheme, found: &[(String, String)], reverted: &HashSet<&str>
+) -> Result<Option<String>> {
+  let mut highest: Option<&str> = None;
+  for (_, vers) in found.iter().filter(|(oid, _)| !reverted.contains(oid.as_str())) {
+    if highest.map(|h| scheme.less_than(h, vers)).transpose()?.unwrap_or(true) {
+      highest = Some(vers);
+    }
+  }
+  Ok(highest.map(|h| h.to_string()))
+}
+
+/// Check if a commit footer names the given project, either by its name or by its ID.
+fn footer_names(footer: &Footer, proj: &Project) -> bool {
+  let name = match footer {
+    Footer::ReleaseAs(name, _) | Footer::Bump(name, _) | Footer::Skip(name) => name
+  };
+  name == proj.name() || name == &proj.id().to_string()
+}
+
 struct LastCommitBuilder<'s, C: StateRead> {
   on_line_commit: Option<String>,
   last_commits: HashMap<ProjectId, String>,
@@ -616,7 +815,7 @@ impl<'s, C: StateRead> LastCommitBuilder<'s, C> {
 
 enum Slicer<'r> {
   Orig(&'r Repo),
-  Slice(FsConfig<PrevFiles<'r>>)
+  Slice(Box<FsConfig<PrevFiles<'r>>>)
 }
 
 impl<'r> Slicer<'r> {
@@ -630,10 +829,10 @@ impl<'r> Slicer<'r> {
   }
 
   pub fn slice_to(&mut self, id: FromTagBuf) -> Result<()> {
-    *self = Slicer::Slice(match self {
+    *self = Slicer::Slice(Box::new(match self {
       Slicer::Orig(repo) => FsConfig::from_slice(repo.slice(id))?,
       Slicer::Slice(fsc) => fsc.slice_to(id)?
-    });
+    }));
     Ok(())
   }
 }
@@ -641,9 +840,15 @@ impl<'r> Slicer<'r> {
 fn find_old_tags<'s, I: Iterator<Item = &'s Project>>(projects: I, prev_tag: &str, repo: &Repo) -> Result<OldTags> {
   let mut by_proj_oid = HashMap::new(); // Map<proj_id, Map<oid, Vec<tag>>>
   let mut proj_ids = HashSet::new();
+  let mut tag_prefixes = HashMap::new();
+  let mut schemes = HashMap::new();
 
   for proj in projects {
     proj_ids.insert(proj.id().clone());
+    schemes.insert(proj.id().clone(), proj.scheme());
+    if let Some(tag_prefix) = proj.tag_prefix() {
+      tag_prefixes.insert(proj.id().clone(), tag_prefix.clone());
+    }
     for fnmatch in tag_fnmatches(proj) {
       trace!("Searching tags for proj {} matching \"{}\".", proj.id(), fnmatch);
       for tag in repo.tag_names(Some(fnmatch.as_str()))?.iter().filter_map(identity) {
@@ -655,13 +860,24 @@ fn find_old_tags<'s, I: Iterator<Item = &'s Project>>(projects: I, prev_tag: &st
     }
   }
 
+  let all = by_proj_oid
+    .iter()
+    .map(|(proj_id, by_id)| {
+      let tags: Vec<_> = by_id.values().flatten().cloned().collect();
+      let tag_prefix = tag_prefixes.get(proj_id).map(|p| p.as_str()).unwrap_or("");
+      (proj_id.clone(), tags_to_versions(&tags, tag_prefix, schemes[proj_id]))
+    })
+    .collect();
+
   let mut current = HashMap::new();
   for commit_oid in repo.commits_to_head(FromTag::new(prev_tag, true), false)?.map(|c| c.map(|c| c.id())) {
     let commit_oid = commit_oid?;
     by_proj_oid.retain(|proj_id, by_id| {
       if let Some(tags) = by_id.remove(&commit_oid) {
-        let mut versions = tags_to_versions(&tags);
-        versions.sort_unstable_by(version_sort);
+        let tag_prefix = tag_prefixes.get(proj_id).map(|p| p.as_str()).unwrap_or("");
+        let scheme = schemes[proj_id];
+        let mut versions = tags_to_versions(&tags, tag_prefix, scheme);
+        versions.sort_unstable_by(|a, b| version_sort(scheme, a, b));
         current.insert(proj_id.clone(), versions[0].clone());
         false
       } else {
@@ -673,7 +889,7 @@ fn find_old_tags<'s, I: Iterator<Item = &'s Project>>(projects: I, prev_tag: &st
   let prev = pull_from_annotation(repo, prev_tag)?;
   fill_from_prev(&prev, &proj_ids, &mut current)?;
 
-  let old_tags = OldTags::new(current, prev);
+  let old_tags = OldTags::new(current, prev, all);
   trace!("Found old tags: {:?}", old_tags);
   Ok(old_tags)
 }
@@ -721,45 +937,45 @@ fn tag_fnmatches(proj: &Project) -> impl Iterator<Item = String> + '_ {
   }
 }
 
-fn tags_to_versions(tags: &[String]) -> Vec<String> {
+/// Extract the versions from a project's tags, which are always in the form "v{version}" or
+/// "{tag_prefix}-v{version}" (see `Project::full_version`). The version itself may contain a dash.
+fn tags_to_versions(tags: &[String], tag_prefix: &str, scheme: &dyn VersionScheme) -> Vec<String> {
+  let lead = if tag_prefix.is_empty() { "v".to_string() } else { format!("{}-v", tag_prefix) };
   tags
     .iter()
-    .map(|tag| {
-      let v = tag.rfind('-').map(|d| d + 1).unwrap_or(0);
-      tag[v + 1 ..].to_string()
-    })
-    .filter(|v| Size::parts(v).is_ok())
+    .filter_map(|tag| tag.strip_prefix(lead.as_str()))
+    .filter(|v| scheme.validate(v).is_ok())
+    .map(|v| v.to_string())
     .collect()
 }
 
-#[allow(clippy::ptr_arg)]
-fn version_sort(a: &String, b: &String) -> Ordering {
-  let p1 = Size::parts(a);
-  let p2 = Size::parts(b);
-
-  if let Ok(p1) = p1 {
-    if let Ok(p2) = p2 {
-      if p1[0] < p2[0] {
-        Ordering::Greater
-      } else if p1[0] > p2[0] {
-        Ordering::Less
-      } else if p1[1] < p2[1] {
-        Ordering::Greater
-      } else if p1[1] > p2[1] {
-        Ordering::Less
-      } else if p1[2] < p2[2] {
-        Ordering::Greater
-      } else if p1[2] > p2[2] {
-        Ordering::Less
-      } else {
-        Ordering::Equal
-      }
-    } else {
-      Ordering::Greater
-    }
-  } else if p2.is_ok() {
-    Ordering::Less
-  } else {
-    Ordering::Equal
+/// Sort versions from highest to lowest, with any invalid versions at the end.
+fn version_sort(scheme: &dyn VersionScheme, a: &str, b: &str) -> Ordering {
+  match (scheme.validate(a).is_ok(), scheme.validate(b).is_ok()) {
+    (true, true) => scheme.compare(b, a).unwrap_or(Ordering::Equal),
+    (true, false) => Ordering::Less,
+    (false, true) => Ordering::Greater,
+    (false, false) => Ordering::Equal
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::highest_release_as;
+  use crate::scheme::Scheme;
+  use std::collections::HashSet;
+
+  #[test]
+  fn test_highest_release_as() {
+    let scheme = Scheme::SemVer.imp();
+    let found = vec![("aaa".to_string(), "2.0.0".to_string()), ("bbb".to_string(), "3.0.0".to_string())];
+
+    assert_eq!(Some("3.0.0".to_string()), highest_release_as(scheme, &found, &HashSet::new()).unwrap());
+
+    let reverted: HashSet<&str> = vec!["bbb"].into_iter().collect();
+    assert_eq!(Some("2.0.0".to_string()), highest_release_as(scheme, &found, &reverted).unwrap());
+
+    let reverted: HashSet<&str> = vec!["aaa", "bbb"].into_iter().collect();
+    assert_eq!(None, highest_release_as(scheme, &found, &reverted).unwrap());
   }
 }
@@ -342,6 +342,9 @@ fn println_plan_incrs(plan: &Plan, mono: &Mono) -> Result<()> {
   for (id, (size, changelog)) in plan.incrs() {
     let curt_proj = mono.get_project(id).unwrap();
     println!("{} : {}", curt_proj.name(), size);
+    if let Some(release_as) = plan.release_as(id) {
+      println!("  Release as {}", release_as);
+    }
 
     let curt_config = mono.config();
     let prev_config = curt_config.slice_to_prev(mono.repo())?;
@@ -353,8 +356,12 @@ fn println_plan_incrs(plan: &Plan, mono: &Mono) -> Result<()> {
 
     if let Some(prev_vers) = prev_vers {
       if size != &Size::Empty {
-        let target = size.apply(&prev_vers)?;
-        if Size::less_than(&curt_vers, &target)? {
+        let size = curt_proj.adjust_size(*size, &prev_vers)?;
+        let target = match plan.release_as(id) {
+          Some(release_as) => release_as.to_string(),
+          None => curt_proj.scheme().apply(size, &prev_vers)?
+        };
+        if curt_proj.scheme().less_than(&curt_vers, &target)? {
           if curt_proj.verify_restrictions(&target).is_err() {
             println!("  ! Illegal size change for restricted project {}.", curt_proj.id());
           }
@@ -0,0 +1,216 @@
+//! Utilities to find a mark in a Gradle build file, written in either the Groovy or Kotlin DSL.
+
+use crate::errors::Result;
+use crate::mark::Mark;
+#[cfg(test)]
+use crate::scan::parts::ToPart;
+use crate::scan::parts::{IntoPartVec, Part};
+use crate::scan::Scanner;
+use error_chain::bail;
+
+pub struct GradleScanner {
+  target: Vec<Part>
+}
+
+impl GradleScanner {
+  pub fn new(target: &str) -> GradleScanner { GradleScanner { target: target.into_part_vec() } }
+
+  #[cfg(test)]
+  pub fn from_parts(target: &[&dyn ToPart]) -> GradleScanner { GradleScanner { target: target.into_part_vec() } }
+}
+
+impl Scanner for GradleScanner {
+  fn build(parts: Vec<Part>) -> GradleScanner { GradleScanner { target: parts } }
+  fn find(&self, data: &str) -> Result<Mark> { Ok(scan_gradle(data, self.target.clone())?.swap_remove(0)) }
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { scan_gradle(data, self.target.clone()) }
+}
+
+/// Find all marks of the parts in the data, in document order.
+///
+/// The parts are joined back together with dots. If the result has a colon, it's treated as a `group:artifact`
+/// dependency coordinate, and the version of every matching `"group:artifact:version"` string is found. Otherwise,
+/// it's the name of a property, and the value of each `name = "value"`, `name "value"`, or `name("value")` is found.
+fn scan_gradle<P: IntoPartVec>(data: &str, loc: P) -> Result<Vec<Mark>> {
+  let parts = loc.into_part_vec();
+  let names = parts
+    .iter()
+    .map(|p| match p {
+      Part::Map(name) => Ok(name.as_str()),
+      other => err!("Only keys are allowed in Gradle spec, not {:?}", other)
+    })
+    .collect::<Result<Vec<_>>>()?;
+  if names.is_empty() {
+    bail!("No parts found for Gradle spec");
+  }
+
+  let target = names.join(".");
+  let tokens = tokenize(data)?;
+  let marks = if target.contains(':') { find_coords(&tokens, &target) } else { find_props(&tokens, &target) };
+
+  if marks.is_empty() {
+    bail!("Couldn't find version in Gradle at {:?}", parts);
+  }
+  Ok(marks)
+}
+
+fn find_props(tokens: &[Token], name: &str) -> Vec<Mark> {
+  tokens
+    .iter()
+    .enumerate()
+    .filter(|(_, t)| matches!(t, Token::Ident(id) if *id == name))
+    // Skip infix uses, like the plugin version in `id("org.acme.plugin") version "1.2.3"`.
+    .filter(|(i, _)| *i == 0 || !matches!(tokens[i - 1], Token::Punct(')') | Token::Str(..)))
+    .filter_map(|(i, _)| match &tokens[i + 1 ..] {
+      [Token::Punct('='), Token::Str(val, start, true), ..]
+      | [Token::Punct('('), Token::Str(val, start, true), Token::Punct(')'), ..]
+      | [Token::Str(val, start, true), ..] => Some(Mark::new(val.to_string(), *start)),
+      _ => None
+    })
+    .collect()
+}
+
+fn find_coords(tokens: &[Token], coord: &str) -> Vec<Mark> {
+  let prefix = format!("{}:", coord);
+  tokens
+    .iter()
+    .filter_map(|t| match t {
+      Token::Str(val, start, true) => {
+        let vers = val.strip_prefix(&prefix)?;
+        let vers = &vers[.. vers.find([':', '@']).unwrap_or(vers.len())];
+        Some(Mark::new(vers.to_string(), start + prefix.len()))
+      }
+      _ => None
+    })
+    .collect()
+}
+
+/// A Gradle token: a dotted identifier, a single punctuation or newline character, or a string literal with its
+/// contents, their start, and if it's free of `$` interpolations.
+enum Token<'a> {
+  Ident(&'a str),
+  Punct(char),
+  Str(&'a str, usize, bool)
+}
+
+fn tokenize(data: &str) -> Result<Vec<Token<'_>>> {
+  let mut tokens = Vec::new();
+  let mut pos = 0;
+
+  while let Some(c) = data[pos ..].chars().next() {
+    let rest = &data[pos ..];
+    if rest.starts_with("//") {
+      pos += rest.find('\n').unwrap_or(rest.len());
+    } else if rest.starts_with("/*") {
+      pos += rest.find("*/").map(|i| i + 2).unwrap_or(rest.len());
+    } else if c == '"' || c == '\'' {
+      let quote = if rest.starts_with("\"\"\"") || rest.starts_with("'''") { &rest[.. 3] } else { &rest[.. 1] };
+      let start = pos + quote.len();
+      let len = find_close(&data[start ..], quote).ok_or_else(|| bad!("Unclosed string in Gradle at {}", pos))?;
+      let val = &data[start .. start + len];
+      tokens.push(Token::Str(val, start, c == '\'' || !val.contains('$')));
+      pos = start + len + quote.len();
+    } else if c.is_alphabetic() || c == '_' {
+      let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.')).unwrap_or(rest.len());
+      tokens.push(Token::Ident(rest[.. len].trim_end_matches('.')));
+      pos += len;
+    } else {
+      // Newlines are kept, since they can end a statement.
+      if c == '\n' || !c.is_whitespace() {
+        tokens.push(Token::Punct(c));
+      }
+      pos += c.len_utf8();
+    }
+  }
+
+  Ok(tokens)
+}
+
+/// Find the end of a string's contents, skipping escaped characters.
+fn find_close(data: &str, quote: &str) -> Option<usize> {
+  let mut chars = data.char_indices();
+  while let Some((i, c)) = chars.next() {
+    if c == '\\' {
+      chars.next();
+    } else if data[i ..].starts_with(quote) {
+      return Some(i);
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod test {
+  use super::GradleScanner;
+  use crate::scan::Scanner;
+
+  #[test]
+  fn test_gradle_groovy() {
+    let doc = r#"
+plugins {
+    id 'java'
+}
+
+group 'org.acme'
+version '1.2.3'
+"#;
+
+    let mark = GradleScanner::new("version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(54, mark.start());
+  }
+
+  #[test]
+  fn test_gradle_kotlin() {
+    let doc = r#"
+plugins {
+    kotlin("jvm") version "1.9.0"
+}
+
+// version = "0.0.1"
+group = "org.acme"
+project.version = "0.0.2"
+version = "1.2.3"
+"#;
+
+    let mark = GradleScanner::new("version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(125, mark.start());
+
+    let mark = GradleScanner::new("project.version").find(doc).unwrap();
+    assert_eq!("0.0.2", mark.value());
+  }
+
+  #[test]
+  fn test_gradle_settings() {
+    let doc = "rootProject.name = \"thing\"\ninclude(\"core\")\n";
+
+    let mark = GradleScanner::new("rootProject.name").find(doc).unwrap();
+    assert_eq!("thing", mark.value());
+    assert_eq!(20, mark.start());
+  }
+
+  #[test]
+  fn test_gradle_coords() {
+    let doc = r#"
+dependencies {
+    implementation("org.acme:core:1.2.3")
+    implementation "org.acme:core-extra:0.0.1"
+    testImplementation 'org.acme:core:1.2.3:tests@jar'
+    implementation("org.acme:core:$coreVersion")
+}
+"#;
+
+    let marks = GradleScanner::new("org.acme:core").find_all(doc).unwrap();
+    let found: Vec<_> = marks.iter().map(|m| (m.value(), m.start())).collect();
+    assert_eq!(vec![("1.2.3", 50), ("1.2.3", 143)], found);
+
+    let mark = GradleScanner::from_parts(&[&"org.acme:core-extra"]).find(doc).unwrap();
+    assert_eq!("0.0.1", mark.value());
+  }
+
+  #[test]
+  fn test_gradle_interpolated() {
+    let doc = "version = \"${base}.1\"\n";
+    assert!(GradleScanner::new("version").find(doc).is_err());
+  }
+}
@@ -0,0 +1,382 @@
+//! Utilities to find a mark in a HCL file, such as a Terraform or Packer configuration.
+
+use crate::errors::Result;
+use crate::mark::Mark;
+#[cfg(test)]
+use crate::scan::parts::ToPart;
+use crate::scan::parts::{IntoPartVec, Part};
+use crate::scan::Scanner;
+use error_chain::bail;
+
+pub struct HclScanner {
+  target: Vec<Part>
+}
+
+impl HclScanner {
+  #[cfg(test)]
+  pub fn new(target: &str) -> HclScanner { HclScanner { target: target.into_part_vec() } }
+
+  #[cfg(test)]
+  pub fn from_parts(target: &[&dyn ToPart]) -> HclScanner { HclScanner { target: target.into_part_vec() } }
+}
+
+impl Scanner for HclScanner {
+  fn build(parts: Vec<Part>) -> HclScanner { HclScanner { target: parts } }
+  fn find(&self, data: &str) -> Result<Mark> { Ok(scan_hcl(data, self.target.clone())?.swap_remove(0)) }
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { scan_hcl(data, self.target.clone()) }
+}
+
+/// Find all marks of the parts in the data, in document order.
+///
+/// A block matches its type followed by all of its labels, so `module.network.version` finds the `version` attribute
+/// of `module "network" { ... }`. Attributes whose values are objects can be further searched by their keys.
+fn scan_hcl<P: IntoPartVec>(data: &str, loc: P) -> Result<Vec<Mark>> {
+  let parts = loc.into_part_vec();
+  let names = parts
+    .iter()
+    .map(|p| match p {
+      Part::Map(name) => Ok(name.as_str()),
+      other => err!("Only keys are allowed in HCL spec, not {:?}", other)
+    })
+    .collect::<Result<Vec<_>>>()?;
+  if names.is_empty() {
+    bail!("No parts found for HCL spec");
+  }
+
+  let body = HclParser::new(data).body(false)?;
+  let mut marks = Vec::new();
+  find_items(&body, &names, &mut marks);
+
+  if marks.is_empty() {
+    bail!("Couldn't find version in HCL at {:?}", parts);
+  }
+  Ok(marks)
+}
+
+enum Item<'a> {
+  Attr(&'a str, Value<'a>),
+  Block(&'a str, Vec<&'a str>, Vec<Item<'a>>)
+}
+
+/// An attribute value: only literal strings and objects are retained.
+enum Value<'a> {
+  Scalar(&'a str, usize),
+  Object(Vec<(&'a str, Value<'a>)>),
+  Other
+}
+
+fn find_items(items: &[Item], parts: &[&str], found: &mut Vec<Mark>) {
+  let (first, rest) = match parts.split_first() {
+    Some(split) => split,
+    None => return
+  };
+
+  for item in items {
+    match item {
+      Item::Attr(name, value) if name == first => find_value(value, rest, found),
+      Item::Block(kind, labels, body) if kind == first && rest.starts_with(labels) => {
+        find_items(body, &rest[labels.len() ..], found)
+      }
+      _ => ()
+    }
+  }
+}
+
+fn find_value(value: &Value, parts: &[&str], found: &mut Vec<Mark>) {
+  match (value, parts) {
+    (Value::Scalar(val, start), []) => found.push(Mark::new(val.to_string(), *start)),
+    (Value::Object(entries), [first, rest @ ..]) => {
+      for (_, value) in entries.iter().filter(|(k, _)| k == first) {
+        find_value(value, rest, found);
+      }
+    }
+    _ => ()
+  }
+}
+
+/// A minimal HCL parser, which keeps the structure of blocks and attributes, and skips over other expressions.
+struct HclParser<'a> {
+  data: &'a str,
+  pos: usize
+}
+
+impl<'a> HclParser<'a> {
+  fn new(data: &'a str) -> HclParser<'a> { HclParser { data, pos: 0 } }
+
+  fn rest(&self) -> &'a str { &self.data[self.pos ..] }
+  fn peek(&self) -> Option<char> { self.rest().chars().next() }
+
+  fn bump(&mut self) -> Option<char> {
+    let c = self.peek()?;
+    self.pos += c.len_utf8();
+    Some(c)
+  }
+
+  /// Skip whitespace and comments, including newlines only if asked.
+  fn skip_space(&mut self, newlines: bool) {
+    loop {
+      let rest = self.rest();
+      if rest.starts_with('#') || rest.starts_with("//") {
+        self.pos += rest.find('\n').unwrap_or(rest.len());
+      } else if rest.starts_with("/*") {
+        self.pos += rest.find("*/").map(|i| i + 2).unwrap_or(rest.len());
+      } else {
+        match self.peek() {
+          Some('\n') if !newlines => return,
+          Some(c) if c.is_whitespace() => self.pos += c.len_utf8(),
+          _ => return
+        }
+      }
+    }
+  }
+
+  fn ident(&mut self) -> Result<&'a str> {
+    let rest = self.rest();
+    let len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-')).unwrap_or(rest.len());
+    if len == 0 {
+      bail!("Expected identifier in HCL at {}", self.pos);
+    }
+    self.pos += len;
+    Ok(&rest[.. len])
+  }
+
+  /// Read a quoted string, returning its raw contents, their start, and if it is free of template interpolations.
+  fn string(&mut self) -> Result<(&'a str, usize, bool)> {
+    self.bump();
+    let start = self.pos;
+    let mut plain = true;
+    loop {
+      match self.bump() {
+        Some('\\') => {
+          self.bump();
+        }
+        Some('"') => return Ok((&self.data[start .. self.pos - 1], start, plain)),
+        Some('$') | Some('%') if self.peek() == Some('{') => {
+          plain = false;
+          self.bump();
+          self.skip_expr()?;
+          self.bump();
+        }
+        Some(_) => (),
+        None => bail!("Unclosed string in HCL at {}", start)
+      }
+    }
+  }
+
+  fn body(&mut self, nested: bool) -> Result<Vec<Item<'a>>> {
+    let mut items = Vec::new();
+    loop {
+      self.skip_space(true);
+      match self.peek() {
+        None if nested => bail!("Unclosed block in HCL"),
+        None => return Ok(items),
+        Some('}') if nested => {
+          self.bump();
+          return Ok(items);
+        }
+        _ => ()
+      }
+
+      let name = self.ident()?;
+      self.skip_space(false);
+      if self.rest().starts_with('=') && !self.rest().starts_with("==") {
+        self.bump();
+        items.push(Item::Attr(name, self.expr()?));
+      } else {
+        let mut labels = Vec::new();
+        loop {
+          self.skip_space(false);
+          match self.peek() {
+            Some('{') => break,
+            Some('"') => labels.push(self.string()?.0),
+            _ => labels.push(self.ident()?)
+          }
+        }
+        self.bump();
+        items.push(Item::Block(name, labels, self.body(true)?));
+      }
+    }
+  }
+
+  fn expr(&mut self) -> Result<Value<'a>> {
+    self.skip_space(false);
+    match self.peek() {
+      Some('"') => {
+        let (val, start, plain) = self.string()?;
+        self.skip_space(false);
+        if plain && matches!(self.peek(), None | Some('\n') | Some(',') | Some('}')) {
+          Ok(Value::Scalar(val, start))
+        } else {
+          self.skip_expr()?;
+          Ok(Value::Other)
+        }
+      }
+      Some('{') => {
+        self.bump();
+        self.object()
+      }
+      _ => {
+        self.skip_expr()?;
+        Ok(Value::Other)
+      }
+    }
+  }
+
+  fn object(&mut self) -> Result<Value<'a>> {
+    let mut entries = Vec::new();
+    loop {
+      self.skip_space(true);
+      let key = match self.peek() {
+        Some('}') => {
+          self.bump();
+          return Ok(Value::Object(entries));
+        }
+        Some(',') => {
+          self.bump();
+          continue;
+        }
+        Some('"') => self.string()?.0,
+        Some(_) => self.ident()?,
+        None => bail!("Unclosed object in HCL")
+      };
+
+      self.skip_space(false);
+      match self.bump() {
+        Some('=') | Some(':') => (),
+        _ => bail!("Expected '=' or ':' after \"{}\" in HCL", key)
+      }
+      entries.push((key, self.expr()?));
+    }
+  }
+
+  /// Skip over the rest of an expression, stopping before the newline, comma, or closing bracket that ends it.
+  fn skip_expr(&mut self) -> Result<()> {
+    let mut depth = 0;
+    loop {
+      let rest = self.rest();
+      if rest.starts_with("<<") {
+        self.skip_heredoc()?;
+        continue;
+      }
+      if rest.starts_with('#') || rest.starts_with("//") || rest.starts_with("/*") {
+        self.skip_space(false);
+        continue;
+      }
+
+      match self.peek() {
+        None => return Ok(()),
+        Some('"') => {
+          self.string()?;
+        }
+        Some('(') | Some('[') | Some('{') => {
+          depth += 1;
+          self.bump();
+        }
+        Some(')') | Some(']') | Some('}') if depth == 0 => return Ok(()),
+        Some(')') | Some(']') | Some('}') => {
+          depth -= 1;
+          self.bump();
+        }
+        Some('\n') | Some(',') if depth == 0 => return Ok(()),
+        Some(_) => {
+          self.bump();
+        }
+      }
+    }
+  }
+
+  fn skip_heredoc(&mut self) -> Result<()> {
+    self.pos += 2;
+    if self.peek() == Some('-') {
+      self.bump();
+    }
+    let marker = self.ident()?;
+    let rest = self.rest();
+    let mut offset = rest.find('\n').map(|i| i + 1).unwrap_or(rest.len());
+    for line in rest[offset ..].split_inclusive('\n') {
+      offset += line.len();
+      if line.trim() == marker {
+        self.pos += offset - (line.len() - line.trim_end().len());
+        return Ok(());
+      }
+    }
+    bail!("Unclosed heredoc \"{}\" in HCL", marker)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::HclScanner;
+  use crate::scan::Scanner;
+
+  #[test]
+  fn test_hcl_module() {
+    let doc = r#"
+# The network module.
+module "network" {
+  source  = "app.terraform.io/example/network/aws"
+  version = "1.2.3"
+}
+"#;
+
+    let mark = HclScanner::new("module.network.version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(106, mark.start());
+  }
+
+  #[test]
+  fn test_hcl_labels() {
+    let doc = r#"
+module "compute" {
+  version = "0.0.1"
+}
+
+module "network" {
+  version = "1.2.3"
+}
+
+terraform {
+  required_version = ">= 1.0.0"
+}
+"#;
+
+    let mark = HclScanner::new("module.network.version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(75, mark.start());
+
+    let mark = HclScanner::new("terraform.required_version").find(doc).unwrap();
+    assert_eq!(">= 1.0.0", mark.value());
+
+    assert!(HclScanner::new("module.storage.version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_hcl_skips() {
+    let doc = r#"
+locals {
+  /* The app name. */
+  name = "app-${var.env}" // interpolated
+  tags = {
+    team = "infra",
+    "owner" = "ops"
+  }
+  policy = <<-EOT
+    version = "9.9.9"
+    EOT
+  list = [
+    "a", "b",
+  ]
+  versions = { app = "1.2.3" }
+}
+"#;
+
+    let mark = HclScanner::new("locals.versions.app").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(228, mark.start());
+
+    let mark = HclScanner::from_parts(&[&"locals", &"tags", &"owner"]).find(doc).unwrap();
+    assert_eq!("ops", mark.value());
+
+    assert!(HclScanner::new("locals.name").find(doc).is_err());
+  }
+}
@@ -0,0 +1,179 @@
+//! Utilities to find a mark in an INI-style key/value file: `.ini`, `.cfg`, Java `.properties`, or dotenv files.
+
+use crate::errors::Result;
+use crate::mark::Mark;
+#[cfg(test)]
+use crate::scan::parts::ToPart;
+use crate::scan::parts::{IntoPartVec, Part};
+use crate::scan::Scanner;
+use error_chain::bail;
+
+pub struct IniScanner {
+  target: Vec<Part>
+}
+
+impl IniScanner {
+  pub fn new(target: &str) -> IniScanner { IniScanner { target: target.into_part_vec() } }
+
+  #[cfg(test)]
+  pub fn from_parts(target: &[&dyn ToPart]) -> IniScanner { IniScanner { target: target.into_part_vec() } }
+}
+
+impl Scanner for IniScanner {
+  fn build(parts: Vec<Part>) -> IniScanner { IniScanner { target: parts } }
+  fn find(&self, data: &str) -> Result<Mark> { Ok(scan_ini(data, self.target.clone())?.swap_remove(0)) }
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { scan_ini(data, self.target.clone()) }
+}
+
+/// Find all marks of the parts in the data, in document order.
+///
+/// Since both section names and keys may contain dots (as in `[tool.bumpversion]` or `app.version=1.2.3`), the parts
+/// may be split anywhere between the section and key: "a.b.c" matches key "c" in section "a.b", key "b.c" in section
+/// "a", or key "a.b.c" outside of any section.
+fn scan_ini<P: IntoPartVec>(data: &str, loc: P) -> Result<Vec<Mark>> {
+  let parts = loc.into_part_vec();
+  let names = parts
+    .iter()
+    .map(|p| match p {
+      Part::Map(name) => Ok(name.as_str()),
+      other => err!("Only keys are allowed in INI spec, not {:?}", other)
+    })
+    .collect::<Result<Vec<_>>>()?;
+  if names.is_empty() {
+    bail!("No parts found for INI spec");
+  }
+
+  let marks: Vec<_> = entries(data)
+    .filter(|e| (0 .. names.len()).any(|i| e.section == names[.. i].join(".") && e.key == names[i ..].join(".")))
+    .map(|e| Mark::new(e.value.to_string(), e.start))
+    .collect();
+
+  if marks.is_empty() {
+    bail!("Couldn't find version in INI at {:?}", parts);
+  }
+  Ok(marks)
+}
+
+struct Entry<'a> {
+  section: &'a str,
+  key: &'a str,
+  value: &'a str,
+  start: usize
+}
+
+/// Iterate over the key/value entries of the data, along with the section in which each is found.
+fn entries(data: &str) -> impl Iterator<Item = Entry<'_>> {
+  let mut section = "";
+  let mut offset = 0;
+
+  data.split_inclusive('\n').filter_map(move |line| {
+    let line_start = offset;
+    offset += line.len();
+
+    let trimmed = line.trim();
+    if trimmed.is_empty() || trimmed.starts_with(['#', ';', '!']) {
+      return None;
+    }
+    if let Some(name) = trimmed.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+      section = name.trim();
+      return None;
+    }
+
+    let sep = line.find(['=', ':'])?;
+    let key = line[.. sep].trim();
+    let key = key.strip_prefix("export ").map(|k| k.trim_start()).unwrap_or(key);
+    let (value, start) = find_value(&line[sep + 1 ..]);
+    Some(Entry { section, key, value, start: line_start + sep + 1 + start })
+  })
+}
+
+/// Find the value in the remainder of a line after its separator, along with its offset in that remainder. Quotes
+/// around the value are excluded, as is a trailing comment on an unquoted value.
+fn find_value(rest: &str) -> (&str, usize) {
+  let start = rest.len() - rest.trim_start().len();
+  let value = rest.trim();
+
+  for quote in ['"', '\''] {
+    if let Some(end) = value.strip_prefix(quote).and_then(|v| v.find(quote)) {
+      return (&value[1 .. end + 1], start + 1);
+    }
+  }
+
+  let end = value
+    .char_indices()
+    .find(|(i, c)| (*c == '#' || *c == ';') && value[.. *i].ends_with(char::is_whitespace))
+    .map(|(i, _)| i)
+    .unwrap_or(value.len());
+  (value[.. end].trim_end(), start)
+}
+
+#[cfg(test)]
+mod test {
+  use super::IniScanner;
+  use crate::scan::Scanner;
+
+  #[test]
+  fn test_properties() {
+    let doc = r#"
+# Gradle properties
+org.gradle.jvmargs=-Xmx2g
+version = 1.2.3
+"#;
+
+    let mark = IniScanner::new("version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(57, mark.start());
+  }
+
+  #[test]
+  fn test_properties_dotted() {
+    let doc = "app.name: thing\napp.version: 1.2.3\n";
+
+    let mark = IniScanner::new("app.version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(29, mark.start());
+  }
+
+  #[test]
+  fn test_ini_section() {
+    let doc = r#"
+[options]
+version = 0.0.1
+
+[metadata]
+name = thing
+; the version
+version = 1.2.3 ; inline comment
+"#;
+
+    let mark = IniScanner::new("metadata.version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(76, mark.start());
+
+    assert!(IniScanner::new("version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_ini_dotted_section() {
+    let doc = "[tool.bumpversion]\ncurrent_version = 1.2.3\n";
+
+    let mark = IniScanner::new("tool.bumpversion.current_version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(37, mark.start());
+
+    let mark = IniScanner::from_parts(&[&"tool.bumpversion", &"current_version"]).find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+  }
+
+  #[test]
+  fn test_dotenv() {
+    let doc = r#"
+export APP_NAME=thing
+export APP_VERSION="1.2.3" # release version
+"#;
+
+    let mark = IniScanner::new("APP_VERSION").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(43, mark.start());
+  }
+}
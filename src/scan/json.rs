@@ -5,8 +5,10 @@ use crate::mark::Mark;
 #[cfg(test)]
 use crate::scan::parts::ToPart;
 use crate::scan::parts::{IntoPartVec, Part};
+use crate::scan::pred::{visit_pred_seq, Found, ValueReader};
 use crate::scan::Scanner;
 use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Unexpected, Visitor};
+use serde_json::Value;
 use std::sync::{Arc, Mutex};
 
 type TraceRef = Arc<Mutex<Trace>>;
@@ -24,21 +26,96 @@ impl JsonScanner {
 
 impl Scanner for JsonScanner {
   fn build(parts: Vec<Part>) -> JsonScanner { JsonScanner { target: parts } }
-  fn find(&self, data: &str) -> Result<Mark> { scan_json(data, self.target.clone()) }
+  fn find(&self, data: &str) -> Result<Mark> { Ok(scan_json(data, self.target.clone())?.swap_remove(0)) }
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { scan_json(data, self.target.clone()) }
 }
 
-fn scan_json<P: IntoPartVec>(data: &str, loc: P) -> Result<Mark> {
+/// A scanner for JSON with comments and trailing commas, as in a `tsconfig.json` or `deno.jsonc`.
+pub struct JsoncScanner {
+  target: Vec<Part>
+}
+
+impl JsoncScanner {
+  #[cfg(test)]
+  pub fn new(target: &str) -> JsoncScanner { JsoncScanner { target: target.into_part_vec() } }
+}
+
+impl Scanner for JsoncScanner {
+  fn build(parts: Vec<Part>) -> JsoncScanner { JsoncScanner { target: parts } }
+  fn find(&self, data: &str) -> Result<Mark> { Ok(scan_json(&strip_jsonc(data)?, self.target.clone())?.swap_remove(0)) }
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { scan_json(&strip_jsonc(data)?, self.target.clone()) }
+}
+
+/// Blank out the comments and trailing commas of JSONC data with spaces, so that it can be parsed as plain JSON while
+/// every other byte stays in place.
+fn strip_jsonc(data: &str) -> Result<String> {
+  let bytes = data.as_bytes();
+  let mut out = bytes.to_vec();
+  let mut last_comma = None;
+  let mut i = 0;
+
+  while i < bytes.len() {
+    match bytes[i] {
+      b'"' => {
+        i += 1;
+        while i < bytes.len() && bytes[i] != b'"' {
+          i += if bytes[i] == b'\\' { 2 } else { 1 };
+        }
+        last_comma = None;
+      }
+      b'/' if bytes.get(i + 1) == Some(&b'/') => {
+        let end = data[i ..].find('\n').map(|e| i + e).unwrap_or(bytes.len());
+        out[i .. end].iter_mut().for_each(|b| *b = b' ');
+        i = end;
+        continue;
+      }
+      b'/' if bytes.get(i + 1) == Some(&b'*') => {
+        let end = data[i + 2 ..].find("*/").map(|e| i + e + 4).unwrap_or(bytes.len());
+        out[i .. end].iter_mut().filter(|b| **b != b'\n').for_each(|b| *b = b' ');
+        i = end;
+        continue;
+      }
+      b',' => last_comma = Some(i),
+      b'}' | b']' => {
+        if let Some(comma) = last_comma.take() {
+          out[comma] = b' ';
+        }
+      }
+      b if b.is_ascii_whitespace() => (),
+      _ => last_comma = None
+    }
+    i += 1;
+  }
+
+  Ok(std::str::from_utf8(&out)?.to_string())
+}
+
+/// Find all marks of the parts in the data, in document order; a predicate may match multiple elements of an array.
+fn scan_json<P: IntoPartVec>(data: &str, loc: P) -> Result<Vec<Mark>> {
   let mut parts = loc.into_part_vec();
   parts.reverse();
 
   let trace = Arc::new(Mutex::new(Trace::new()));
   let reader = MeteredReader::new(data.as_bytes(), trace.clone());
 
-  let value = pop(parts, trace.clone()).deserialize(&mut serde_json::Deserializer::from_reader(reader))?;
-  let index = trace.lock()?.find_start()?;
+  let values = pop(parts, trace).deserialize(&mut serde_json::Deserializer::from_reader(reader))?;
+  Ok(values.into_iter().map(|(value, index)| Mark::new(value, index)).collect())
+}
 
-  let mark = Mark::new(value, index);
-  Ok(mark)
+/// Read a string value while tracing its bytes, and find where it starts.
+fn read_traced<E, F>(trace: &TraceRef, read: F) -> std::result::Result<(String, usize), E>
+where
+  E: de::Error,
+  F: FnOnce() -> std::result::Result<String, E>
+{
+  trace.lock().unwrap().set_active(true);
+  let value = read();
+  let mut trace = trace.lock().unwrap();
+  trace.set_active(false);
+  let value = value?;
+  let start = trace.find_start().map_err(|e| de::Error::custom(e.to_string()))?;
+  trace.clear();
+  Ok((value, start))
 }
 
 fn pop(mut parts: Vec<Part>, trace: TraceRef) -> NthElement {
@@ -46,6 +123,30 @@ fn pop(mut parts: Vec<Part>, trace: TraceRef) -> NthElement {
   NthElement::new(part, parts, trace)
 }
 
+/// Reads the values of predicate-selected elements, tracing the found values to find where they start.
+#[derive(Clone)]
+struct JsonReader(TraceRef);
+
+impl ValueReader for JsonReader {
+  fn read_value<'de, V>(&self, map: &mut V, remains: Vec<Part>) -> std::result::Result<Found, V::Error>
+  where
+    V: MapAccess<'de>
+  {
+    if remains.is_empty() {
+      Ok(vec![read_traced(&self.0, || map.next_value())?])
+    } else {
+      map.next_value_seed(pop(remains, self.0.clone()))
+    }
+  }
+
+  fn read_text<'de, V: MapAccess<'de>>(&self, map: &mut V) -> std::result::Result<String, V::Error> {
+    match map.next_value()? {
+      Value::String(s) => Ok(s),
+      other => Ok(other.to_string())
+    }
+  }
+}
+
 struct NthElement {
   part: Part,
   remains: Vec<Part>,
@@ -57,7 +158,7 @@ impl NthElement {
 }
 
 impl<'de> Visitor<'de> for NthElement {
-  type Value = String;
+  type Value = Found;
 
   fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
     write!(formatter, "a part that is {:?}", self.part)
@@ -72,15 +173,12 @@ impl<'de> Visitor<'de> for NthElement {
       _ => return Err(de::Error::invalid_type(Unexpected::Map, &self))
     };
 
-    let mut got_val: Option<String> = None;
+    let mut got_val: Option<Found> = None;
 
     while let Some(key) = map.next_key::<String>()? {
       if key == expected_key {
         let nth = if self.remains.is_empty() {
-          self.trace.lock().unwrap().set_active(true);
-          let r = map.next_value()?;
-          self.trace.lock().unwrap().set_active(false);
-          r
+          vec![read_traced(&self.trace, || map.next_value())?]
         } else {
           let next = pop(std::mem::replace(&mut self.remains, Vec::new()), self.trace.clone());
           map.next_value_seed(next)?
@@ -105,6 +203,7 @@ impl<'de> Visitor<'de> for NthElement {
   {
     let n = match &self.part {
       Part::Seq(n) => *n,
+      Part::Pred(_) => return visit_pred_seq(&self.part, &self.remains, &JsonReader(self.trace.clone()), seq),
       _ => return Err(de::Error::invalid_type(Unexpected::Seq, &self))
     };
 
@@ -115,10 +214,8 @@ impl<'de> Visitor<'de> for NthElement {
     }
 
     let nth = if self.remains.is_empty() {
-      self.trace.lock().unwrap().set_active(true);
-      let r = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(n, &self))?;
-      self.trace.lock().unwrap().set_active(false);
-      r
+      let read = || seq.next_element()?.ok_or_else(|| de::Error::invalid_length(n, &self));
+      vec![read_traced(&self.trace, read)?]
     } else {
       let next = pop(std::mem::replace(&mut self.remains, Vec::new()), self.trace.clone());
       seq.next_element_seed(next)?.ok_or_else(|| de::Error::invalid_length(n, &self))?
@@ -131,7 +228,7 @@ impl<'de> Visitor<'de> for NthElement {
 }
 
 impl<'de> DeserializeSeed<'de> for NthElement {
-  type Value = String;
+  type Value = Found;
 
   fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
   where
@@ -155,6 +252,7 @@ impl Trace {
   pub fn new() -> Trace { Trace { active: false, leader: 0, bytes: Vec::new() } }
 
   pub fn set_active(&mut self, active: bool) { self.active = active; }
+  pub fn clear(&mut self) { self.bytes.clear(); }
 
   pub fn accept(&mut self, buf: &[u8], amt: usize, leader: usize) {
     if self.active {
@@ -192,7 +290,7 @@ impl<'a> std::io::Read for MeteredReader<'a> {
 
 #[cfg(test)]
 mod test {
-  use super::JsonScanner;
+  use super::{JsonScanner, JsoncScanner};
   use crate::scan::Scanner;
 
   #[test]
@@ -256,6 +354,52 @@ mod test {
     assert_eq!(51, mark.start());
   }
 
+  #[test]
+  fn test_json_pred() {
+    let doc = r#"
+{
+  "dependencies": [
+    "ignored",
+    { "version": "0.0.1", "name": "other" },
+    { "version": "1.2.3", "name": "core", "optional": false }
+  ]
+}"#;
+
+    let mark = JsonScanner::new("dependencies[name=core].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(101, mark.start());
+
+    let mark = JsonScanner::new("dependencies[name=core][optional=false].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+
+    assert!(JsonScanner::new("dependencies[name=none].version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_json_pred_all() {
+    let doc = r#"
+{
+  "packages": [
+    { "name": "core", "version": "1.2.3" },
+    { "name": "other", "version": "0.0.1" },
+    { "version": "1.2.3", "name": "core" }
+  ]
+}"#;
+
+    let marks = JsonScanner::new("packages[name=core].version").find_all(doc).unwrap();
+    assert_eq!(vec![("1.2.3", 53), ("1.2.3", 126)], marks.iter().map(|m| (m.value(), m.start())).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_json_index() {
+    let doc = r#"
+{ "list": [ "0.0.1", "1.2.3" ] }"#;
+
+    let mark = JsonScanner::new("list[1]").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(23, mark.start());
+  }
+
   #[test]
   fn test_json_utf8() {
     let doc = r#"
@@ -271,4 +415,35 @@ mod test {
     assert_eq!("1.2.3", mark.value());
     assert_eq!(39, mark.start());
   }
+
+  #[test]
+  fn test_jsonc() {
+    let doc = r#"{
+  // The "version" is "0.0.1"
+  "name": "thing", /* not "version": "0.0.2", */
+  "version": "1.2.3",
+  "files": [
+    "lib", // trailing
+  ],
+}"#;
+
+    let mark = JsoncScanner::new("version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(95, mark.start());
+
+    assert!(JsonScanner::new("version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_jsonc_pred_all() {
+    let doc = r#"{
+  "packages": [
+    { "name": "core", "version": "1.2.3" }, // first
+    { "name": "core", "version": "1.2.3" }, // second
+  ]
+}"#;
+
+    let marks = JsoncScanner::new("packages[name=core].version").find_all(doc).unwrap();
+    assert_eq!(vec![52, 105], marks.iter().map(|m| m.start()).collect::<Vec<_>>());
+  }
 }
@@ -1,45 +1,85 @@
+mod gradle;
+mod hcl;
+mod ini;
 mod json;
 pub mod parts;
+mod pred;
 mod toml;
 mod xml;
 mod yaml;
 
-pub use self::json::JsonScanner;
+pub use self::gradle::GradleScanner;
+pub use self::hcl::HclScanner;
+pub use self::ini::IniScanner;
+pub use self::json::{JsonScanner, JsoncScanner};
 pub use self::toml::TomlScanner;
 pub use self::xml::XmlScanner;
 pub use self::yaml::YamlScanner;
 use crate::errors::Result;
 use crate::mark::{Mark, MarkedData, NamedData};
 use crate::scan::parts::Part;
-use regex::Regex;
+use error_chain::bail;
+use regex::{Captures, Match, Regex};
 
 pub trait Scanner {
   fn build(parts: Vec<Part>) -> Self;
 
   fn find(&self, data: &str) -> Result<Mark>;
 
-  fn find_version(&self, data: &str) -> Result<Mark> {
-    let mark = self.find(data)?;
-    mark.validate_version()?;
-    Ok(mark)
-  }
+  /// Find every match in the data. Scanners that can't find more than one match just find the first.
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { Ok(vec![self.find(data)?]) }
 
   fn scan(&self, data: NamedData) -> Result<MarkedData> {
     let mark = self.find(data.data())?;
     Ok(data.mark(mark))
   }
+
+  fn scan_all(&self, data: NamedData) -> Result<MarkedData> {
+    let marks = self.find_all(data.data())?;
+    Ok(data.mark_all(marks))
+  }
 }
 
 pub fn find_reg_data(data: &str, pattern: &str) -> Result<Mark> {
   let pattern = Regex::new(pattern)?;
   let found = pattern.captures(data).ok_or_else(|| bad!("No match for {}", pattern))?;
-  let item = found.get(1).ok_or_else(|| bad!("No capture group in {}.", pattern))?;
+  let item = version_group(&pattern, &found)?;
   let value = item.as_str().to_string();
   let index = item.start();
   Ok(Mark::new(value, index))
 }
 
+pub fn find_all_reg_data(data: &str, pattern: &str) -> Result<Vec<Mark>> {
+  let pattern = Regex::new(pattern)?;
+  let marks = pattern
+    .captures_iter(data)
+    .map(|found| {
+      let item = version_group(&pattern, &found)?;
+      Ok(Mark::new(item.as_str().to_string(), item.start()))
+    })
+    .collect::<Result<Vec<_>>>()?;
+
+  if marks.is_empty() {
+    bail!("No match for {}", pattern);
+  }
+  Ok(marks)
+}
+
+/// The capture that holds the version: the group named `version` if the pattern has one, or else the first group.
+fn version_group<'t>(pattern: &Regex, found: &Captures<'t>) -> Result<Match<'t>> {
+  if pattern.capture_names().any(|n| n == Some("version")) {
+    found.name("version").ok_or_else(|| bad!("No version captured by {}.", pattern))
+  } else {
+    found.get(1).ok_or_else(|| bad!("No capture group in {}.", pattern))
+  }
+}
+
 pub fn scan_reg_data(data: NamedData, pattern: &str) -> Result<MarkedData> {
   let mark = find_reg_data(data.data(), pattern)?;
   Ok(data.mark(mark))
 }
+
+pub fn scan_all_reg_data(data: NamedData, pattern: &str) -> Result<MarkedData> {
+  let marks = find_all_reg_data(data.data(), pattern)?;
+  Ok(data.mark_all(marks))
+}
@@ -13,7 +13,7 @@ impl IntoPartVec for Vec<Part> {
 }
 
 impl IntoPartVec for &str {
-  fn into_part_vec(self) -> Vec<Part> { self.split('.').map(parse_part).collect() }
+  fn into_part_vec(self) -> Vec<Part> { split_parts(self).into_iter().flat_map(parse_segment).collect() }
 }
 
 impl IntoPartVec for &[&dyn ToPart] {
@@ -27,6 +27,73 @@ pub fn parse_part(part: &str) -> Part {
   }
 }
 
+/// Split a target on its dots, except for dots inside of brackets (as in `dependency[groupId=org.example]`).
+fn split_parts(target: &str) -> Vec<&str> {
+  let mut segments = Vec::new();
+  let mut depth = 0;
+  let mut start = 0;
+  for (i, c) in target.char_indices() {
+    match c {
+      '[' => depth += 1,
+      ']' if depth > 0 => depth -= 1,
+      '.' if depth == 0 => {
+        segments.push(&target[start .. i]);
+        start = i + 1;
+      }
+      _ => ()
+    }
+  }
+  segments.push(&target[start ..]);
+  segments
+}
+
+/// Parse a single segment of a target, which may have any number of bracketed selectors following its name, as in
+/// `dependency[2]` or `dependency[artifactId=core]`.
+fn parse_segment(segment: &str) -> Vec<Part> {
+  let name_end = match segment.find('[') {
+    Some(i) if segment.ends_with(']') => i,
+    _ => return vec![parse_part(segment)]
+  };
+
+  let mut parts = Vec::new();
+  if name_end > 0 {
+    parts.push(parse_part(&segment[.. name_end]));
+  }
+
+  for selector in segment[name_end + 1 .. segment.len() - 1].split("][") {
+    match (parse_selector(selector), parts.last_mut()) {
+      (Some(Part::Pred(mut new_conds)), Some(Part::Pred(conds))) => conds.append(&mut new_conds),
+      (Some(part), _) => parts.push(part),
+      (None, _) => return vec![parse_part(segment)]
+    }
+  }
+  parts
+}
+
+fn parse_selector(selector: &str) -> Option<Part> {
+  if let Ok(i) = selector.trim().parse() {
+    return Some(Part::Seq(i));
+  }
+
+  let eq = selector.find('=')?;
+  let field = selector[.. eq].trim();
+  let value = unquote(selector[eq + 1 ..].trim());
+  if field.is_empty() {
+    None
+  } else {
+    Some(Part::Pred(vec![(field.to_string(), value.to_string())]))
+  }
+}
+
+fn unquote(v: &str) -> &str {
+  for q in &['"', '\''] {
+    if v.len() >= 2 && v.starts_with(*q) && v.ends_with(*q) {
+      return &v[1 .. v.len() - 1];
+    }
+  }
+  v
+}
+
 pub trait ToPart {
   fn to_part(&self) -> Part;
 }
@@ -43,29 +110,25 @@ impl ToPart for usize {
   fn to_part(&self) -> Part { Part::Seq(*self) }
 }
 
-#[derive(Clone, Debug)]
+/// A single step in finding a value in a structured file.
+///
+/// `Seq` selects an element of a sequence by its (zero-based) index, and `Map` selects a value in a map by its key.
+/// `Pred` selects the first element of a sequence which is a map, and whose values at the given keys are the given
+/// values: as in `dependencies[name=core]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Part {
   Seq(usize),
-  Map(String)
+  Map(String),
+  Pred(Vec<(String, String)>)
 }
 
 impl Part {
-  pub fn seq_ind(&self) -> usize {
+  /// Check if the given lookup function finds all the values required by this predicate part.
+  pub fn is_pred_match<'a, F: Fn(&str) -> Option<&'a str>>(&self, lookup: F) -> bool {
     match self {
-      Part::Seq(i) => *i,
-      _ => panic!("Part is not seq")
-    }
-  }
-}
-
-pub fn is_match_str(key: &str, part: Option<&Part>) -> bool {
-  if let Some(part) = part {
-    match part {
-      Part::Map(k) => key == k,
+      Part::Pred(conds) => conds.iter().all(|(k, v)| lookup(k) == Some(v.as_str())),
       _ => false
     }
-  } else {
-    false
   }
 }
 
@@ -116,7 +179,14 @@ impl<'de> Deserialize<'de> for Part {
       fn visit_f32<E: de::Error>(self, v: f32) -> std::result::Result<Self::Value, E> { Ok(Part::Seq(v as usize)) }
       fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<Self::Value, E> { Ok(Part::Seq(v as usize)) }
 
-      fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Self::Value, E> { Ok(Part::Map(v.to_string())) }
+      fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<Self::Value, E> {
+        if v.starts_with('[') {
+          if let [part] = parse_segment(v).as_slice() {
+            return Ok(part.clone());
+          }
+        }
+        Ok(Part::Map(v.to_string()))
+      }
     }
 
     desr.deserialize_any(PartVisitor)
@@ -127,7 +197,48 @@ impl Serialize for Part {
   fn serialize<S: Serializer>(&self, ser: S) -> std::result::Result<S::Ok, S::Error> {
     match self {
       Part::Seq(v) => v.serialize(ser),
-      Part::Map(v) => v.serialize(ser)
+      Part::Map(v) => v.serialize(ser),
+      Part::Pred(conds) => {
+        let conds: Vec<_> = conds.iter().map(|(k, v)| format!("[{}={}]", k, v)).collect();
+        conds.join("").serialize(ser)
+      }
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::{IntoPartVec, Part};
+
+  #[test]
+  fn test_parts_simple() {
+    let parts = "project.dependencies.1.version".into_part_vec();
+    let expected = vec![
+      Part::Map("project".into()),
+      Part::Map("dependencies".into()),
+      Part::Seq(1),
+      Part::Map("version".into())
+    ];
+    assert_eq!(expected, parts);
+  }
+
+  #[test]
+  fn test_parts_selectors() {
+    let parts = "dependency[groupId=org.example][artifactId='core'].version".into_part_vec();
+    let expected = vec![
+      Part::Map("dependency".into()),
+      Part::Pred(vec![("groupId".into(), "org.example".into()), ("artifactId".into(), "core".into())]),
+      Part::Map("version".into())
+    ];
+    assert_eq!(expected, parts);
+
+    let parts = "dependency[2].version".into_part_vec();
+    let expected = vec![Part::Map("dependency".into()), Part::Seq(2), Part::Map("version".into())];
+    assert_eq!(expected, parts);
+  }
+
+  #[test]
+  fn test_parts_not_selector() {
+    assert_eq!(vec![Part::Map("a[b]".into())], "a[b]".into_part_vec());
+  }
+}
@@ -0,0 +1,120 @@
+//! A predicate part over the elements of a sequence, shared by the scanners that deserialize their data.
+
+use crate::scan::parts::Part;
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use std::collections::HashMap;
+
+/// The values found by a scanner, each with its start in the data.
+pub type Found = Vec<(String, usize)>;
+
+/// How a scanner reads the entries of a map in an element selected by a predicate.
+pub trait ValueReader: Clone {
+  /// Read the value of the current map entry: the value itself if there are no remaining parts, or else whatever the
+  /// remaining parts select in it.
+  fn read_value<'de, V: MapAccess<'de>>(&self, map: &mut V, remains: Vec<Part>) -> Result<Found, V::Error>;
+
+  /// Read the value of the current map entry as text, to be matched against the predicate.
+  fn read_text<'de, V: MapAccess<'de>>(&self, map: &mut V) -> Result<String, V::Error>;
+}
+
+/// Visit a sequence, finding the values in every element that matches the predicate part. The remaining parts are
+/// in reverse order, so that the next part is last.
+pub fn visit_pred_seq<'de, R, V>(part: &Part, remains: &[Part], reader: &R, mut seq: V) -> Result<Found, V::Error>
+where
+  R: ValueReader,
+  V: SeqAccess<'de>
+{
+  if remains.is_empty() {
+    return Err(de::Error::custom(format!("Predicate {:?} must be followed by a key", part)));
+  }
+
+  let mut found = Vec::new();
+  loop {
+    let elem = PredElement { part: part.clone(), remains: remains.to_vec(), reader: reader.clone() };
+    match seq.next_element_seed(elem)? {
+      Some(result) => found.extend(result),
+      None => break
+    }
+  }
+
+  if found.is_empty() {
+    return Err(de::Error::custom(format!("No element matches {:?}", part)));
+  }
+  Ok(found)
+}
+
+/// Visits a single element of a sequence (such as a table in an array of tables), which only produces values if it
+/// matches the predicate part.
+struct PredElement<R> {
+  part: Part,
+  remains: Vec<Part>,
+  reader: R
+}
+
+impl<'de, R: ValueReader> Visitor<'de> for PredElement<R> {
+  type Value = Found;
+
+  fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+    write!(formatter, "an element that matches {:?}", self.part)
+  }
+
+  fn visit_map<V>(mut self, mut map: V) -> Result<Self::Value, V::Error>
+  where
+    V: MapAccess<'de>
+  {
+    let next_key = match self.remains.pop() {
+      Some(Part::Map(key)) => key,
+      other => return Err(de::Error::custom(format!("Expected a key after predicate, not {:?}", other)))
+    };
+    let pred_keys: Vec<&str> = match &self.part {
+      Part::Pred(conds) => conds.iter().map(|(k, _)| k.as_str()).collect(),
+      _ => Vec::new()
+    };
+
+    let mut values = HashMap::new();
+    let mut got_val: Option<Found> = None;
+
+    while let Some(key) = map.next_key::<String>()? {
+      if key == next_key {
+        got_val = Some(self.reader.read_value(&mut map, std::mem::take(&mut self.remains))?);
+      } else if pred_keys.contains(&key.as_str()) {
+        let value = self.reader.read_text(&mut map)?;
+        values.insert(key, value);
+      } else {
+        map.next_value::<IgnoredAny>()?;
+      }
+    }
+
+    if self.part.is_pred_match(|k| values.get(k).map(|v| v.as_str())) {
+      got_val.ok_or_else(|| de::Error::missing_field("<missing field>"))
+    } else {
+      Ok(Vec::new())
+    }
+  }
+
+  fn visit_seq<V>(self, mut seq: V) -> Result<Self::Value, V::Error>
+  where
+    V: SeqAccess<'de>
+  {
+    while let Some(IgnoredAny) = seq.next_element()? {}
+    Ok(Vec::new())
+  }
+
+  fn visit_str<E: de::Error>(self, _: &str) -> Result<Self::Value, E> { Ok(Vec::new()) }
+  fn visit_i64<E: de::Error>(self, _: i64) -> Result<Self::Value, E> { Ok(Vec::new()) }
+  fn visit_u64<E: de::Error>(self, _: u64) -> Result<Self::Value, E> { Ok(Vec::new()) }
+  fn visit_f64<E: de::Error>(self, _: f64) -> Result<Self::Value, E> { Ok(Vec::new()) }
+  fn visit_bool<E: de::Error>(self, _: bool) -> Result<Self::Value, E> { Ok(Vec::new()) }
+  fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> { Ok(Vec::new()) }
+}
+
+impl<'de, R: ValueReader> DeserializeSeed<'de> for PredElement<R> {
+  type Value = Found;
+
+  fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+  where
+    D: Deserializer<'de>
+  {
+    deserializer.deserialize_any(self)
+  }
+}
@@ -5,9 +5,10 @@ use crate::mark::Mark;
 #[cfg(test)]
 use crate::scan::parts::ToPart;
 use crate::scan::parts::{IntoPartVec, Part};
+use crate::scan::pred::{visit_pred_seq, Found, ValueReader};
 use crate::scan::Scanner;
 use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Unexpected, Visitor};
-use toml::Spanned;
+use toml::{Spanned, Value};
 
 pub struct TomlScanner {
   target: Vec<Part>
@@ -22,17 +23,35 @@ impl TomlScanner {
 
 impl Scanner for TomlScanner {
   fn build(parts: Vec<Part>) -> TomlScanner { TomlScanner { target: parts } }
-  fn find(&self, data: &str) -> Result<Mark> { scan_toml(data, self.target.clone()) }
+  fn find(&self, data: &str) -> Result<Mark> { Ok(scan_toml(data, self.target.clone())?.swap_remove(0)) }
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { scan_toml(data, self.target.clone()) }
 }
 
-fn scan_toml<P: IntoPartVec>(data: &str, loc: P) -> Result<Mark> {
+/// Find all marks of the parts in the data, in document order; a predicate may match multiple tables of an array.
+fn scan_toml<P: IntoPartVec>(data: &str, loc: P) -> Result<Vec<Mark>> {
   let mut parts = loc.into_part_vec();
   parts.reverse();
 
-  let value = pop(parts).deserialize(&mut toml::Deserializer::new(data))?;
-  let index = value.span().0;
+  let values = pop(parts).deserialize(&mut toml::Deserializer::new(data))?;
+  Ok(values.into_iter().map(|(value, index)| to_mark(data, value, index)).collect())
+}
+
+fn to_mark(data: &str, value: String, index: usize) -> Mark {
+  let rest = &data[index ..];
+
+  if rest.starts_with("\"\"\"") || rest.starts_with("'''") {
+    // A newline immediately after the opening quotes of a multi-line string isn't part of its value.
+    let newline = ["\r\n", "\n"].iter().find(|nl| rest[3 ..].starts_with(*nl)).map(|nl| nl.len()).unwrap_or(0);
+    Mark::new(value.trim_end().to_string(), index + 3 + newline)
+  } else {
+    Mark::new(value, index + 1)
+  }
+}
 
-  Ok(Mark::new(value.into_inner(), index + 1))
+/// Take the value and start of a spanned string.
+fn unspan(value: Spanned<String>) -> (String, usize) {
+  let start = value.start();
+  (value.into_inner(), start)
 }
 
 fn pop(mut parts: Vec<Part>) -> NthElement {
@@ -40,6 +59,30 @@ fn pop(mut parts: Vec<Part>) -> NthElement {
   NthElement::new(part, parts)
 }
 
+/// Reads the values of predicate-selected elements, which are spanned to find where they start.
+#[derive(Clone)]
+struct TomlReader;
+
+impl ValueReader for TomlReader {
+  fn read_value<'de, V>(&self, map: &mut V, remains: Vec<Part>) -> std::result::Result<Found, V::Error>
+  where
+    V: MapAccess<'de>
+  {
+    if remains.is_empty() {
+      Ok(vec![unspan(map.next_value()?)])
+    } else {
+      map.next_value_seed(pop(remains))
+    }
+  }
+
+  fn read_text<'de, V: MapAccess<'de>>(&self, map: &mut V) -> std::result::Result<String, V::Error> {
+    match map.next_value()? {
+      Value::String(s) => Ok(s),
+      other => Ok(other.to_string())
+    }
+  }
+}
+
 pub struct NthElement {
   part: Part,
   remains: Vec<Part>
@@ -50,7 +93,7 @@ impl NthElement {
 }
 
 impl<'de> Visitor<'de> for NthElement {
-  type Value = Spanned<String>;
+  type Value = Found;
 
   fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
     write!(formatter, "a part that is {:?}", self.part)
@@ -65,12 +108,12 @@ impl<'de> Visitor<'de> for NthElement {
       _ => return Err(de::Error::invalid_type(Unexpected::Map, &self))
     };
 
-    let mut got_val: Option<Spanned<String>> = None;
+    let mut got_val: Option<Found> = None;
 
     while let Some(key) = map.next_key::<String>()? {
       if key == expected_key {
         let nth = if self.remains.is_empty() {
-          map.next_value()?
+          vec![unspan(map.next_value()?)]
         } else {
           let next = pop(std::mem::replace(&mut self.remains, Vec::new()));
           map.next_value_seed(next)?
@@ -95,6 +138,7 @@ impl<'de> Visitor<'de> for NthElement {
   {
     let n = match &self.part {
       Part::Seq(n) => *n,
+      Part::Pred(_) => return visit_pred_seq(&self.part, &self.remains, &TomlReader, seq),
       _ => return Err(de::Error::invalid_type(Unexpected::Seq, &self))
     };
 
@@ -105,7 +149,7 @@ impl<'de> Visitor<'de> for NthElement {
     }
 
     let nth = if self.remains.is_empty() {
-      seq.next_element()?.ok_or_else(|| de::Error::invalid_length(n, &self))?
+      vec![unspan(seq.next_element()?.ok_or_else(|| de::Error::invalid_length(n, &self))?)]
     } else {
       let next = pop(std::mem::replace(&mut self.remains, Vec::new()));
       seq.next_element_seed(next)?.ok_or_else(|| de::Error::invalid_length(n, &self))?
@@ -118,7 +162,7 @@ impl<'de> Visitor<'de> for NthElement {
 }
 
 impl<'de> DeserializeSeed<'de> for NthElement {
-  type Value = Spanned<String>;
+  type Value = Found;
 
   fn deserialize<D>(self, deserializer: D) -> std::result::Result<Self::Value, D::Error>
   where
@@ -175,6 +219,76 @@ thing = [ "thing2", "1.2.3" ]"#;
     assert_eq!(24, mark.start());
   }
 
+  #[test]
+  fn test_toml_multiline() {
+    let doc = r#"
+basic = """
+1.2.3"""
+literal = '''4.5.6
+'''"#;
+
+    let mark = TomlScanner::new("basic").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(13, mark.start());
+
+    let mark = TomlScanner::new("literal").find(doc).unwrap();
+    assert_eq!("4.5.6", mark.value());
+    assert_eq!(35, mark.start());
+  }
+
+  #[test]
+  fn test_toml_inline_table() {
+    let doc = r#"
+[dependencies]
+serde = "1.0"
+core = { version = "1.2.3", path = "../core" }"#;
+
+    let mark = TomlScanner::new("dependencies.core.version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(50, mark.start());
+  }
+
+  #[test]
+  fn test_toml_pred() {
+    let doc = r#"
+[[package]]
+name = "other"
+version = "0.0.1"
+
+[[package]]
+name = "core"
+version = "1.2.3"
+dependencies = ["other"]"#;
+
+    let mark = TomlScanner::new("package[name=core].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(84, mark.start());
+
+    let mark = TomlScanner::new("package[1].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+
+    assert!(TomlScanner::new("package[name=none].version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_toml_pred_all() {
+    let doc = r#"
+[[package]]
+name = "core"
+version = "1.2.3"
+
+[[package]]
+name = "other"
+version = "0.0.1"
+
+[[package]]
+name = "core"
+version = """1.2.3""""#;
+
+    let marks = TomlScanner::new("package[name=core].version").find_all(doc).unwrap();
+    assert_eq!(vec![("1.2.3", 38), ("1.2.3", 131)], marks.iter().map(|m| (m.value(), m.start())).collect::<Vec<_>>());
+  }
+
   #[test]
   fn test_toml_utf8() {
     let doc = r#"
@@ -4,10 +4,10 @@ use crate::errors::Result;
 use crate::mark::Mark;
 #[cfg(test)]
 use crate::scan::parts::ToPart;
-use crate::scan::parts::{is_match_str, IntoPartVec, Part};
+use crate::scan::parts::{IntoPartVec, Part};
 use crate::scan::Scanner;
 use error_chain::bail;
-use xmlparser::{ElementEnd, Token, Tokenizer};
+use xmlparser::{ElementEnd, StrSpan, Token, Tokenizer};
 
 pub struct XmlScanner {
   target: Vec<Part>
@@ -22,53 +22,128 @@ impl XmlScanner {
 
 impl Scanner for XmlScanner {
   fn build(parts: Vec<Part>) -> XmlScanner { XmlScanner { target: parts } }
-  fn find(&self, data: &str) -> Result<Mark> { scan_xml(data, self.target.clone()) }
+  fn find(&self, data: &str) -> Result<Mark> { Ok(scan_xml(data, self.target.clone())?.swap_remove(0)) }
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> { scan_xml(data, self.target.clone()) }
 }
 
-fn scan_xml<P: IntoPartVec>(data: &str, loc: P) -> Result<Mark> {
-  let mut parts = loc.into_part_vec();
-  parts.reverse();
-
+/// Find all marks of the parts in the data, in document order.
+fn scan_xml<P: IntoPartVec>(data: &str, loc: P) -> Result<Vec<Mark>> {
+  let parts = loc.into_part_vec();
   if parts.is_empty() {
     bail!("No parts found for XML spec");
   }
 
-  let mut extra_depth = 0;
-  let mut on_target = false;
+  let root = parse_tree(data)?;
+  let marks = find_in(&root.children, &parts);
+  if marks.is_empty() {
+    bail!("Couldn't find version in XML at {:?}", parts);
+  }
+  Ok(marks)
+}
+
+/// A minimal XML element, which keeps only what's needed to find a value.
+#[derive(Default)]
+struct Element<'a> {
+  prefix: &'a str,
+  local: &'a str,
+  attrs: Vec<(&'a str, &'a str, StrSpan<'a>)>,
+  children: Vec<Element<'a>>,
+  text: Option<StrSpan<'a>>
+}
+
+impl<'a> Element<'a> {
+  fn new(prefix: &'a str, local: &'a str) -> Element<'a> { Element { prefix, local, ..Default::default() } }
+
+  /// Find the value of a predicate key in this element: either an attribute (as in `@id`) or the text of a child.
+  fn lookup(&self, key: &str) -> Option<&'a str> {
+    match key.strip_prefix('@') {
+      Some(key) => self.attrs.iter().find(|(p, l, _)| is_match_name(p, l, key)).map(|(.., v)| v.as_str()),
+      None => {
+        let child = self.children.iter().find(|c| is_match_name(c.prefix, c.local, key));
+        child.and_then(|c| c.text).map(|t| t.as_str())
+      }
+    }
+  }
+}
+
+fn parse_tree(data: &str) -> Result<Element<'_>> {
+  let mut stack = vec![Element::default()];
 
   for token in Tokenizer::from(data) {
     match token? {
-      Token::ElementStart { local, .. } => {
-        if extra_depth == 0 && is_match_str(local.as_str(), parts.last()) {
-          parts.pop();
-          if parts.is_empty() {
-            on_target = true;
-          }
-        } else {
-          extra_depth += 1;
-        }
+      Token::ElementStart { prefix, local, .. } => stack.push(Element::new(prefix.as_str(), local.as_str())),
+      Token::Attribute { prefix, local, value, .. } => {
+        stack.last_mut().unwrap().attrs.push((prefix.as_str(), local.as_str(), value));
       }
       Token::ElementEnd { end, .. } if is_ending(&end) => {
-        if extra_depth > 0 {
-          extra_depth -= 1;
-        } else {
-          bail!("Couldn't find version in XML: still expecting {:?}", parts);
+        if stack.len() < 2 {
+          bail!("Unbalanced XML: unexpected element end.");
         }
+        let elem = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(elem);
       }
-      Token::Text { text } => {
-        if on_target {
-          return Ok(Mark::new(text.as_str().into(), text.start()));
-        }
+      Token::Text { text } if !text.as_str().trim().is_empty() => {
+        stack.last_mut().unwrap().text.get_or_insert(text);
+      }
+      Token::Cdata { text, .. } => {
+        stack.last_mut().unwrap().text.get_or_insert(text);
       }
       _ => ()
     }
   }
 
-  bail!("Couldn't find version at end of XML: still expecting {:?}", parts)
+  if stack.len() != 1 {
+    bail!("Unbalanced XML: unclosed element.");
+  }
+  Ok(stack.pop().unwrap())
+}
+
+/// Find every match of the parts among the elements, searching all matching elements for the rest of the parts.
+fn find_in(elems: &[Element], parts: &[Part]) -> Vec<Mark> {
+  let (name, rest) = match parts.split_first() {
+    Some((Part::Map(name), rest)) if !name.starts_with('@') => (name, rest),
+    _ => return Vec::new()
+  };
+
+  let mut candidates: Vec<_> = elems.iter().filter(|e| is_match_name(e.prefix, e.local, name)).collect();
+  let mut rest = rest;
+  loop {
+    match rest.first() {
+      Some(Part::Seq(i)) => candidates = candidates.get(*i).into_iter().copied().collect(),
+      Some(pred @ Part::Pred(_)) => candidates.retain(|e| pred.is_pred_match(|k| e.lookup(k))),
+      _ => break
+    }
+    rest = &rest[1 ..];
+  }
+
+  candidates.into_iter().flat_map(|e| find_at(e, rest)).collect()
+}
+
+fn find_at(elem: &Element, parts: &[Part]) -> Vec<Mark> {
+  match parts {
+    [] => elem.text.map(|t| Mark::new(t.as_str().into(), t.start())).into_iter().collect(),
+    [Part::Map(attr)] if attr.starts_with('@') => elem
+      .attrs
+      .iter()
+      .find(|(p, l, _)| is_match_name(p, l, &attr[1 ..]))
+      .map(|(.., v)| Mark::new(v.as_str().into(), v.start()))
+      .into_iter()
+      .collect(),
+    _ => find_in(&elem.children, parts)
+  }
 }
 
 fn is_ending(end: &ElementEnd) -> bool { matches!(end, ElementEnd::Close(..) | ElementEnd::Empty) }
 
+/// Match an element or attribute name against a key. A key with a namespace prefix (as in `android:versionName`)
+/// only matches that prefix; otherwise, only the local name is matched.
+fn is_match_name(prefix: &str, local: &str, key: &str) -> bool {
+  match key.find(':') {
+    Some(i) => &key[.. i] == prefix && &key[i + 1 ..] == local,
+    None => key == local
+  }
+}
+
 #[cfg(test)]
 mod test {
   use super::XmlScanner;
@@ -110,6 +185,121 @@ mod test {
     assert_eq!(21, mark.start());
   }
 
+  #[test]
+  fn test_xml_attr() {
+    let doc = r#"
+<Project Sdk="Microsoft.NET.Sdk">
+  <PropertyGroup Label="first"/>
+  <PropertyGroup Label="second" Version="1.2.3">
+  </PropertyGroup>
+</Project>"#;
+
+    let mark = XmlScanner::new("Project.PropertyGroup.@Version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(109, mark.start());
+  }
+
+  #[test]
+  fn test_xml_attr_namespace() {
+    let doc = r#"
+<manifest xmlns:android="http://schemas.android.com/apk/res/android"
+    versionName="0.0.0"
+    android:versionName="1.2.3">
+</manifest>"#;
+
+    let mark = XmlScanner::new("manifest.@android:versionName").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(119, mark.start());
+
+    let mark = XmlScanner::new("manifest.@versionName").find(doc).unwrap();
+    assert_eq!("0.0.0", mark.value());
+  }
+
+  #[test]
+  fn test_xml_attr_missing() {
+    let doc = r#"
+<package><metadata id="thing"/></package>"#;
+
+    assert!(XmlScanner::new("package.metadata.@version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_xml_namespace() {
+    let doc = r#"
+<package><ns:version>0.0.0</ns:version><other:version>1.2.3</other:version></package>"#;
+
+    let mark = XmlScanner::new("package.other:version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+  }
+
+  #[test]
+  fn test_xml_cdata() {
+    let doc = r#"
+<package>
+  <version>
+    <![CDATA[1.2.3]]>
+  </version>
+</package>"#;
+
+    let mark = XmlScanner::new("package.version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(36, mark.start());
+  }
+
+  #[test]
+  fn test_xml_pred() {
+    let doc = r#"
+<project>
+  <dependencies>
+    <dependency>
+      <groupId>org.example</groupId>
+      <version>0.0.1</version>
+      <artifactId>other</artifactId>
+    </dependency>
+    <dependency id="second">
+      <version>1.2.3</version>
+      <groupId>org.example</groupId>
+      <artifactId>core</artifactId>
+    </dependency>
+  </dependencies>
+</project>"#;
+
+    let spec = "project.dependencies.dependency[groupId=org.example][artifactId=core].version";
+    let mark = XmlScanner::new(spec).find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(212, mark.start());
+
+    let mark = XmlScanner::new("project.dependencies.dependency[@id=second].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+
+    assert!(XmlScanner::new("project.dependencies.dependency[artifactId=none].version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_xml_find_all() {
+    let doc = r#"<project>
+  <module><name>core</name><version>1.2.3</version></module>
+  <module><name>other</name><version>0.0.1</version></module>
+  <module><name>core</name><version>1.2.3</version></module>
+</project>"#;
+
+    let marks = XmlScanner::new("project.module[name=core].version").find_all(doc).unwrap();
+    let found: Vec<_> = marks.iter().map(|m| (m.value(), m.start())).collect();
+    assert_eq!(vec![("1.2.3", 46), ("1.2.3", 169)], found);
+  }
+
+  #[test]
+  fn test_xml_index() {
+    let doc = r#"
+<list><item>0.0.1</item><item>0.0.2</item><item>1.2.3</item></list>"#;
+
+    let mark = XmlScanner::new("list.item[2]").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(49, mark.start());
+
+    assert!(XmlScanner::new("list.item[3]").find(doc).is_err());
+  }
+
   #[test]
   fn test_xml_utf8() {
     let doc = r#"
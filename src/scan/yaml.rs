@@ -6,6 +6,8 @@ use crate::mark::{CharMark, Mark};
 use crate::scan::parts::ToPart;
 use crate::scan::parts::{IntoPartVec, Part};
 use crate::scan::Scanner;
+use error_chain::bail;
+use std::collections::HashMap;
 use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
 use yaml_rust::scanner::{Marker, TScalarStyle};
 
@@ -14,7 +16,6 @@ pub struct YamlScanner {
 }
 
 impl YamlScanner {
-  #[cfg(test)]
   pub fn new(target: &str) -> YamlScanner { YamlScanner { target: target.into_part_vec() } }
 
   #[cfg(test)]
@@ -24,182 +25,190 @@ impl YamlScanner {
 impl Scanner for YamlScanner {
   fn build(parts: Vec<Part>) -> YamlScanner { YamlScanner { target: parts } }
   fn find(&self, data: &str) -> Result<Mark> {
-    let char_mark = scan_yaml(data, self.target.clone())?;
+    let char_mark = scan_yaml(data, self.target.clone())?.swap_remove(0);
     char_mark.into_byte_mark(data)
   }
+
+  fn find_all(&self, data: &str) -> Result<Vec<Mark>> {
+    scan_yaml(data, self.target.clone())?.into_iter().map(|m| m.into_byte_mark(data)).collect()
+  }
 }
 
-fn scan_yaml<P: IntoPartVec>(data: &str, loc: P) -> Result<CharMark> {
-  let mut rcvr = Receiver::new(loc.into_part_vec());
+/// Find all marks of the parts in the data, in document order.
+///
+/// If the data has multiple documents, a leading index or predicate (as in `[1].version` or `[kind=Deployment].spec`)
+/// selects among them; otherwise, only the first document is searched.
+fn scan_yaml<P: IntoPartVec>(data: &str, loc: P) -> Result<Vec<CharMark>> {
+  let parts = loc.into_part_vec();
+  let mut rcvr = Receiver::new();
   let mut parser = Parser::new(data.chars());
 
-  parser.load(&mut rcvr, false)?;
+  parser.load(&mut rcvr, true)?;
 
-  Ok(rcvr.result.unwrap())
-}
+  let docs = rcvr.docs;
+  if docs.is_empty() {
+    bail!("No YAML document found.");
+  }
 
-struct Receiver {
-  parts: Vec<Part>,
-  stack: Vec<Loc>,
-  depth: usize,
-  result: Option<CharMark>
-}
+  let (roots, rest): (Vec<&Node>, _) = match parts.split_first() {
+    Some((Part::Seq(i), rest)) if docs.len() > 1 => (docs.get(*i).into_iter().collect(), rest),
+    Some((pred @ Part::Pred(_), rest)) if docs.len() > 1 => {
+      (docs.iter().filter(|d| pred.is_pred_match(|k| d.lookup(k))).collect(), rest)
+    }
+    _ => (vec![&docs[0]], &parts[..])
+  };
 
-impl Receiver {
-  pub fn new(parts: Vec<Part>) -> Receiver { Receiver { parts, stack: Vec::new(), depth: 0, result: None } }
+  let mut nodes = Vec::new();
+  for root in roots {
+    find_nodes(root, rest, &mut nodes);
+  }
+  if nodes.is_empty() {
+    bail!("Couldn't find YAML value at {:?}.", parts);
+  }
 
-  fn found_key(&mut self) { self.depth += 1; }
+  nodes
+    .into_iter()
+    .map(|node| match node {
+      Node::Scalar(val, index) => Ok(CharMark::new(val.clone(), *index)),
+      // A block scalar can only be written in place if its value is exactly what's in the document.
+      Node::Block(val, index) if data.chars().skip(*index).take(val.chars().count()).eq(val.chars()) => {
+        Ok(CharMark::new(val.clone(), *index))
+      }
+      Node::Block(..) => err!("YAML value at {:?} is a block scalar of more than one line.", parts),
+      _ => err!("YAML value at {:?} isn't a scalar.", parts)
+    })
+    .collect()
+}
 
-  fn before_object(&mut self) {
-    if self.stack.is_empty() {
-      return;
-    }
+/// A YAML node, where scalars retain their character index in the original document.
+#[derive(Clone, Debug)]
+enum Node {
+  Scalar(String, usize),
+  Block(String, usize),
+  Seq(Vec<Node>),
+  Map(Vec<(Node, Node)>)
+}
 
-    let i = match self.stack.last().unwrap() {
-      Loc::Seq(i) => Some(*i),
+impl Node {
+  fn scalar(&self) -> Option<&str> {
+    match self {
+      Node::Scalar(val, _) | Node::Block(val, _) => Some(val),
       _ => None
+    }
+  }
+
+  /// Get the value of a key in a map, including any keys merged in with `<<`.
+  fn get(&self, key: &str) -> Option<&Node> {
+    let entries = match self {
+      Node::Map(entries) => entries,
+      _ => return None
     };
 
-    if let Some(i) = i {
-      if self.stack.len() == self.depth + 1 && self.parts.get(self.depth).unwrap().seq_ind() == i {
-        self.depth += 1;
-      }
-    }
+    entries.iter().find(|(k, _)| k.scalar() == Some(key)).map(|(_, v)| v).or_else(|| {
+      entries.iter().filter(|(k, _)| k.scalar() == Some("<<")).find_map(|(_, v)| match v {
+        Node::Seq(merged) => merged.iter().find_map(|m| m.get(key)),
+        merged => merged.get(key)
+      })
+    })
   }
 
-  fn after_object(&mut self, unnest: bool) {
-    if unnest && self.stack.len() == self.depth {
-      panic!("Completed seq/map without finding value.");
-    }
+  /// Find the scalar value of a predicate key, which may be a dotted path (as in `metadata.name`).
+  fn lookup(&self, key: &str) -> Option<&str> {
+    self.get(key).or_else(|| key.split('.').try_fold(self, |node, k| node.get(k)))?.scalar()
+  }
+}
 
-    if self.stack.is_empty() {
-      return;
+/// Collect every node that matches the parts; a predicate may match multiple elements of a sequence.
+fn find_nodes<'n>(node: &'n Node, parts: &[Part], found: &mut Vec<&'n Node>) {
+  let (part, rest) = match parts.split_first() {
+    Some(split) => split,
+    None => return found.push(node)
+  };
+
+  match (part, node) {
+    (Part::Map(key), _) => {
+      if let Some(node) = node.get(key) {
+        find_nodes(node, rest, found);
+      }
     }
-
-    match self.stack.last_mut().unwrap() {
-      Loc::Map(val) => {
-        *val = !*val;
+    (Part::Seq(i), Node::Seq(elems)) => {
+      if let Some(node) = elems.get(*i) {
+        find_nodes(node, rest, found);
       }
-      Loc::Seq(i) => {
-        *i += 1;
+    }
+    (Part::Pred(_), Node::Seq(elems)) => {
+      for elem in elems.iter().filter(|e| part.is_pred_match(|k| e.lookup(k))) {
+        find_nodes(elem, rest, found);
       }
     }
+    _ => ()
   }
+}
 
-  fn expecting(&self) -> Expect {
-    if self.stack.len() < self.depth {
-      panic!("Not as much stack as expected.")
-    } else if self.stack.len() == self.depth {
-      if self.parts.len() <= self.depth {
-        Expect::Scalar
-      } else {
-        match self.parts.get(self.depth).unwrap() {
-          Part::Map(_) => Expect::Map,
-          Part::Seq(_) => Expect::Seq
-        }
-      }
-    } else if self.stack.len() == self.depth + 1 {
-      match self.parts.get(self.depth).unwrap() {
-        Part::Map(key) => {
-          if !self.stack.last().unwrap().is_map_value() {
-            Expect::Key(key.clone())
-          } else {
-            Expect::None
-          }
-        }
-        _ => Expect::None
+/// Builds a tree of nodes for each document from the parser events. An alias is resolved to a copy of its anchored
+/// node, so that its scalars still point to their location in the anchor.
+struct Receiver {
+  stack: Vec<Building>,
+  anchors: HashMap<usize, Node>,
+  docs: Vec<Node>
+}
+
+enum Building {
+  Seq(Vec<Node>, usize),
+  Map(Vec<(Node, Node)>, Option<Node>, usize)
+}
+
+impl Receiver {
+  pub fn new() -> Receiver { Receiver { stack: Vec::new(), anchors: HashMap::new(), docs: Vec::new() } }
+
+  fn add(&mut self, node: Node, anchor: usize) {
+    if anchor > 0 {
+      self.anchors.insert(anchor, node.clone());
+    }
+
+    match self.stack.last_mut() {
+      None => self.docs.push(node),
+      Some(Building::Seq(elems, _)) => elems.push(node),
+      Some(Building::Map(entries, key, _)) => match key.take() {
+        Some(key) => entries.push((key, node)),
+        None => *key = Some(node)
       }
-    } else {
-      Expect::None
     }
   }
 }
 
 impl MarkedEventReceiver for Receiver {
   fn on_event(&mut self, ev: Event, mark: Marker) {
-    if self.result.is_some() {
-      return;
-    }
-
     match ev {
-      Event::MappingStart(_) => {
-        self.before_object();
-        match self.expecting() {
-          Expect::Map => (),
-          Expect::None => (),
-          _ => panic!("Got unexpected map.")
-        }
-        self.stack.push(Loc::Map(false));
-      }
-      Event::MappingEnd => {
-        self.stack.pop();
-        self.after_object(true);
-      }
-      Event::SequenceStart(_) => {
-        self.before_object();
-        match self.expecting() {
-          Expect::Seq => (),
-          Expect::None => (),
-          _ => panic!("Got unexpected seq.")
-        }
-        self.stack.push(Loc::Seq(0));
+      Event::MappingStart(anchor) => self.stack.push(Building::Map(Vec::new(), None, anchor)),
+      Event::SequenceStart(anchor) => self.stack.push(Building::Seq(Vec::new(), anchor)),
+      Event::MappingEnd | Event::SequenceEnd => {
+        let (node, anchor) = match self.stack.pop() {
+          Some(Building::Seq(elems, anchor)) => (Node::Seq(elems), anchor),
+          Some(Building::Map(entries, _, anchor)) => (Node::Map(entries), anchor),
+          None => return
+        };
+        self.add(node, anchor);
       }
-      Event::SequenceEnd => {
-        self.stack.pop();
-        self.after_object(true);
+      Event::Scalar(val, style, anchor, _tag) => {
+        let node = match style {
+          TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted => Node::Scalar(val, mark.index() + 1),
+          // The mark of a block scalar is already at its content, but its value may have a trailing newline.
+          TScalarStyle::Literal | TScalarStyle::Foled => Node::Block(val.trim().to_string(), mark.index()),
+          _ => Node::Scalar(val, mark.index())
+        };
+        self.add(node, anchor);
       }
-      Event::Scalar(val, style, _anchor, _tag) => {
-        self.before_object();
-        match self.expecting() {
-          Expect::Scalar => {
-            let mut index = mark.index();
-            match style {
-              TScalarStyle::SingleQuoted | TScalarStyle::DoubleQuoted => {
-                index += 1;
-              }
-              _ => ()
-            }
-            self.result = Some(CharMark::new(val, index));
-          }
-          Expect::Key(key) => {
-            if val == key {
-              self.found_key();
-            }
-          }
-          Expect::None => (),
-          _ => panic!("Got unexpected scalar \"{}\". {}, {:?}", val, self.depth, self.stack)
+      Event::Alias(anchor) => {
+        if let Some(node) = self.anchors.get(&anchor).cloned() {
+          self.add(node, 0);
         }
-        self.after_object(false);
       }
       _ => ()
     }
   }
 }
 
-#[derive(Debug)]
-enum Loc {
-  Map(bool),
-  Seq(usize)
-}
-
-impl Loc {
-  fn is_map_value(&self) -> bool {
-    match self {
-      Loc::Map(v) => *v,
-      _ => panic!("Loc is not map")
-    }
-  }
-}
-
-#[derive(Debug)]
-enum Expect {
-  Map,
-  Seq,
-  Scalar,
-  Key(String),
-  None
-}
-
 #[cfg(test)]
 mod test {
   use super::{scan_yaml, YamlScanner};
@@ -209,7 +218,7 @@ mod test {
   fn test_yaml() {
     let doc = r#"version: 1.2.3"#;
 
-    let char_mark = scan_yaml(doc, "version").unwrap();
+    let char_mark = scan_yaml(doc, "version").unwrap().swap_remove(0);
     assert_eq!("1.2.3", char_mark.value());
     assert_eq!(9, char_mark.char_start());
   }
@@ -232,7 +241,7 @@ thing:
   - version: 1.2.3
   - this is long"#;
 
-    let char_mark = scan_yaml(doc, "thing.3.other_x").unwrap();
+    let char_mark = scan_yaml(doc, "thing.3.other_x").unwrap().swap_remove(0);
     assert_eq!("2.4.6", char_mark.value());
     assert_eq!(122, char_mark.char_start());
   }
@@ -260,6 +269,122 @@ package:
     assert_eq!(24, mark.start());
   }
 
+  #[test]
+  fn test_yaml_pred() {
+    let doc = r#"
+dependencies:
+  - name: other
+    version: 0.0.1
+  - version: "1.2.3"
+    name: core
+"#;
+
+    let mark = YamlScanner::new("dependencies[name=core].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(64, mark.start());
+
+    let mark = YamlScanner::new("dependencies[1].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+
+    assert!(YamlScanner::new("dependencies[name=none].version").find(doc).is_err());
+  }
+
+  #[test]
+  fn test_yaml_find_all() {
+    let doc = r#"
+dependencies:
+  - name: core
+    version: 1.2.3
+  - name: other
+    version: 0.0.1
+  - name: core
+    version: "1.2.3"
+"#;
+
+    let marks = YamlScanner::new("dependencies[name=core].version").find_all(doc).unwrap();
+    let found: Vec<_> = marks.iter().map(|m| (m.value(), m.start())).collect();
+    assert_eq!(vec![("1.2.3", 43), ("1.2.3", 113)], found);
+  }
+
+  #[test]
+  fn test_yaml_documents() {
+    let doc = r#"
+kind: Service
+version: 0.0.1
+---
+kind: Deployment
+metadata:
+  name: web
+version: 1.2.3
+"#;
+
+    let mark = YamlScanner::new("version").find(doc).unwrap();
+    assert_eq!("0.0.1", mark.value());
+
+    let mark = YamlScanner::new("[1].version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(82, mark.start());
+
+    let mark = YamlScanner::new("[kind=Deployment].version").find(doc).unwrap();
+    assert_eq!(82, mark.start());
+
+    let mark = YamlScanner::new("[metadata.name=web].version").find(doc).unwrap();
+    assert_eq!(82, mark.start());
+  }
+
+  #[test]
+  fn test_yaml_alias() {
+    let doc = r#"
+base: &base
+  version: &vers 1.2.3
+app:
+  version: *vers
+merged:
+  <<: *base
+  name: merged
+"#;
+
+    let mark = YamlScanner::new("app.version").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(30, mark.start());
+
+    let mark = YamlScanner::new("merged.version").find(doc).unwrap();
+    assert_eq!(30, mark.start());
+  }
+
+  #[test]
+  fn test_yaml_block() {
+    let doc = r#"
+literal: |
+  1.2.3
+folded: >-
+    4.5.6
+"#;
+
+    let mark = YamlScanner::new("literal").find(doc).unwrap();
+    assert_eq!("1.2.3", mark.value());
+    assert_eq!(14, mark.start());
+
+    let mark = YamlScanner::new("folded").find(doc).unwrap();
+    assert_eq!("4.5.6", mark.value());
+    assert_eq!(35, mark.start());
+  }
+
+  #[test]
+  fn test_yaml_block_multiline() {
+    let doc = r#"
+literal: |
+  1.2.3
+  extra
+folded: >
+  4.5.6
+  extra
+"#;
+
+    assert!(YamlScanner::new("literal").find(doc).is_err());
+    assert!(YamlScanner::new("folded").find(doc).is_err());
+  }
+
   #[test]
   fn test_yaml_clever() {
     let doc = r#"
@@ -0,0 +1,543 @@
+//! Version schemes, which define how a project's version numbers are validated, compared, and incremented.
+
+use crate::config::Size;
+use crate::errors::Result;
+use crate::version::SemVer;
+use chrono::{Datelike, Utc};
+use error_chain::bail;
+use regex::Regex;
+use serde::Deserialize;
+use std::cmp::Ordering;
+
+/// The operations that `release` and the planner need from a version scheme.
+pub trait VersionScheme {
+  /// Verify that the value is a legal version in this scheme.
+  fn validate(&self, v: &str) -> Result<()>;
+
+  /// Compare two versions by precedence.
+  fn compare(&self, v1: &str, v2: &str) -> Result<Ordering>;
+
+  /// The major part of a version, which is used to restrict tag majors.
+  fn major(&self, v: &str) -> Result<u32>;
+
+  /// Increment the version by the given size.
+  fn apply(&self, size: Size, v: &str) -> Result<String>;
+
+  /// Increment the version by the given size, but produce a pre-release on the given channel instead of a final
+  /// version. The pre-release number is one past the highest such pre-release found in `existing`.
+  fn apply_pre(&self, _size: Size, v: &str, _channel: &str, _existing: &[&str]) -> Result<String> {
+    bail!("Can't create a pre-release of {}: pre-releases aren't supported by its scheme.", v)
+  }
+
+  /// Convert a pre-release version into its final version.
+  fn promote(&self, v: &str) -> Result<String> {
+    bail!("Can't promote {}: pre-releases aren't supported by its scheme.", v)
+  }
+
+  fn less_than(&self, v1: &str, v2: &str) -> Result<bool> { Ok(self.compare(v1, v2)? == Ordering::Less) }
+}
+
+/// The version scheme of a project, as named in the config file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+pub enum Scheme {
+  #[serde(rename = "semver")]
+  SemVer,
+  #[serde(rename = "calver")]
+  CalVer,
+  #[serde(rename = "two_part")]
+  TwoPart,
+  #[serde(rename = "four_part")]
+  FourPart,
+  #[serde(rename = "pep440")]
+  Pep440,
+  #[serde(rename = "maven")]
+  Maven
+}
+
+impl Scheme {
+  pub fn imp(self) -> &'static dyn VersionScheme {
+    match self {
+      Scheme::SemVer => &SemVerScheme,
+      Scheme::CalVer => &CalVerScheme,
+      Scheme::TwoPart => &NumericScheme { len: 2 },
+      Scheme::FourPart => &NumericScheme { len: 4 },
+      Scheme::Pep440 => &Pep440Scheme,
+      Scheme::Maven => &MavenScheme
+    }
+  }
+}
+
+/// Full SemVer 2.0: `major.minor.patch`, with optional pre-release and build metadata.
+pub struct SemVerScheme;
+
+impl VersionScheme for SemVerScheme {
+  fn validate(&self, v: &str) -> Result<()> { SemVer::parse(v).map(|_| ()) }
+  fn compare(&self, v1: &str, v2: &str) -> Result<Ordering> { Ok(SemVer::parse(v1)?.precedence(&SemVer::parse(v2)?)) }
+  fn major(&self, v: &str) -> Result<u32> { Ok(SemVer::parse(v)?.parts()[0]) }
+
+  /// An increment that fits within a pre-release just drops the pre-release: `1.3.0-rc.1` becomes `1.3.0` for a
+  /// minor or patch increment, and `2.0.0-rc.1` becomes `2.0.0` for any increment. Build metadata is dropped.
+  fn apply(&self, size: Size, v: &str) -> Result<String> {
+    let vers = SemVer::parse(v)?;
+    let [major, minor, patch] = vers.parts();
+    let pre = vers.is_prerelease();
+
+    let newv = match size {
+      Size::Major if pre && minor == 0 && patch == 0 => SemVer::new(major, 0, 0),
+      Size::Major => SemVer::new(major + 1, 0, 0),
+      Size::Minor if pre && patch == 0 => SemVer::new(major, minor, 0),
+      Size::Minor => SemVer::new(major, minor + 1, 0),
+      Size::Patch if pre => SemVer::new(major, minor, patch),
+      Size::Patch => SemVer::new(major, minor, patch + 1),
+      Size::None | Size::Empty => vers,
+      Size::Fail => bail!("'fail' size encountered.")
+    };
+
+    Ok(newv.to_string())
+  }
+
+  fn apply_pre(&self, size: Size, v: &str, channel: &str, existing: &[&str]) -> Result<String> {
+    match size {
+      Size::None | Size::Empty => Ok(v.to_string()),
+      _ => Ok(SemVer::parse(&self.apply(size, v)?)?.next_pre(channel, existing.iter().copied()).to_string())
+    }
+  }
+
+  fn promote(&self, v: &str) -> Result<String> {
+    let vers = SemVer::parse(v)?;
+    if !vers.is_prerelease() {
+      bail!("Version {} is not a pre-release.", v);
+    }
+    Ok(vers.core().to_string())
+  }
+}
+
+/// Calendar versioning in the form `YYYY.MM.MICRO`: any increment moves to the current year and month, and
+/// increments the micro part if the year and month haven't changed.
+pub struct CalVerScheme;
+
+impl CalVerScheme {
+  fn parse(v: &str) -> Result<[u64; 3]> {
+    let parts = parse_numeric(v)?;
+    if parts.len() != 3 || parts[0] < 1000 || parts[1] < 1 || parts[1] > 12 {
+      bail!("Not a YYYY.MM.MICRO version: {}", v);
+    }
+    Ok([parts[0], parts[1], parts[2]])
+  }
+
+  /// Apply a size, keeping any zero padding of the month and micro parts (so `2020.06.2` becomes `2020.06.3`).
+  fn apply_on(size: Size, v: &str, year: u64, month: u64) -> Result<String> {
+    let [y, m, micro] = CalVerScheme::parse(v)?;
+    let widths: Vec<_> = v.split('.').map(|p| if p.starts_with('0') { p.len() } else { 1 }).collect();
+    let (mw, uw) = (widths[1], widths[2]);
+    match size {
+      Size::None | Size::Empty => Ok(v.to_string()),
+      Size::Fail => bail!("'fail' size encountered."),
+      _ if y == year && m == month => Ok(format!("{}.{:0mw$}.{:0uw$}", y, m, micro + 1, mw = mw, uw = uw)),
+      _ => Ok(format!("{}.{:0mw$}.{:0uw$}", year, month, 0, mw = mw, uw = uw))
+    }
+  }
+}
+
+impl VersionScheme for CalVerScheme {
+  fn validate(&self, v: &str) -> Result<()> { CalVerScheme::parse(v).map(|_| ()) }
+
+  fn compare(&self, v1: &str, v2: &str) -> Result<Ordering> {
+    Ok(CalVerScheme::parse(v1)?.cmp(&CalVerScheme::parse(v2)?))
+  }
+
+  fn major(&self, v: &str) -> Result<u32> { Ok(CalVerScheme::parse(v)?[0] as u32) }
+
+  fn apply(&self, size: Size, v: &str) -> Result<String> {
+    let now = Utc::now();
+    CalVerScheme::apply_on(size, v, now.year() as u64, now.month() as u64)
+  }
+}
+
+/// A fixed number of numeric parts, such as two-part `major.minor` or four-part .NET `major.minor.build.revision`.
+/// A patch increment increments the third part if there is one, or else the last part.
+pub struct NumericScheme {
+  len: usize
+}
+
+impl NumericScheme {
+  fn parse(&self, v: &str) -> Result<Vec<u64>> {
+    let parts = parse_numeric(v)?;
+    if parts.len() != self.len {
+      bail!("Not a {}-part version: {}", self.len, v);
+    }
+    Ok(parts)
+  }
+}
+
+impl VersionScheme for NumericScheme {
+  fn validate(&self, v: &str) -> Result<()> { self.parse(v).map(|_| ()) }
+  fn compare(&self, v1: &str, v2: &str) -> Result<Ordering> { Ok(self.parse(v1)?.cmp(&self.parse(v2)?)) }
+  fn major(&self, v: &str) -> Result<u32> { Ok(self.parse(v)?[0] as u32) }
+
+  fn apply(&self, size: Size, v: &str) -> Result<String> {
+    let mut parts = self.parse(v)?;
+    let index = match size {
+      Size::Major => 0,
+      Size::Minor => 1,
+      Size::Patch => std::cmp::min(2, self.len - 1),
+      Size::None | Size::Empty => return Ok(v.to_string()),
+      Size::Fail => bail!("'fail' size encountered.")
+    };
+
+    bump(&mut parts, index);
+    Ok(join_numeric(&parts))
+  }
+}
+
+/// Python's PEP 440 versions, in their normalized form: `[N!]N(.N)*[{a|b|rc}N][.postN][.devN]`.
+pub struct Pep440Scheme;
+
+#[derive(Debug, PartialEq)]
+struct Pep440 {
+  epoch: u64,
+  release: Vec<u64>,
+  pre: Option<(String, u64)>,
+  post: Option<u64>,
+  dev: Option<u64>
+}
+
+impl Pep440 {
+  fn parse(v: &str) -> Result<Pep440> {
+    let regex = Regex::new(r"^(?:(\d+)!)?(\d+(?:\.\d+)*)(?:(a|b|rc)(\d+))?(?:\.post(\d+))?(?:\.dev(\d+))?$")?;
+    let caps = regex.captures(v).ok_or_else(|| bad!("Not a PEP 440 version: {}", v))?;
+    let num = |i: usize| caps.get(i).map(|m| m.as_str().parse::<u64>()).transpose();
+
+    Ok(Pep440 {
+      epoch: num(1)?.unwrap_or(0),
+      release: parse_numeric(&caps[2])?,
+      pre: caps.get(3).map(|k| k.as_str().to_string()).zip(num(4)?),
+      post: num(5)?,
+      dev: num(6)?
+    })
+  }
+
+  fn is_prerelease(&self) -> bool { self.pre.is_some() || self.dev.is_some() }
+
+  fn final_of(&self, release: Vec<u64>) -> Pep440 {
+    Pep440 { epoch: self.epoch, release, pre: None, post: None, dev: None }
+  }
+
+  /// The release segment with trailing zeros removed, since `1.2` and `1.2.0` are equivalent.
+  fn trimmed(&self) -> &[u64] {
+    let len = self.release.iter().rposition(|n| *n != 0).map(|i| i + 1).unwrap_or(0);
+    &self.release[.. len]
+  }
+
+  #[allow(clippy::type_complexity)]
+  fn key(&self) -> (u64, &[u64], (i64, u64), Option<u64>, (bool, u64)) {
+    let pre = match &self.pre {
+      Some((kind, n)) => (pep440_pre_rank(kind), *n),
+      None if self.post.is_none() && self.dev.is_some() => (-1, 0),
+      None => (3, 0)
+    };
+    let dev = (self.dev.is_none(), self.dev.unwrap_or(0));
+    (self.epoch, self.trimmed(), pre, self.post, dev)
+  }
+}
+
+impl std::fmt::Display for Pep440 {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    if self.epoch != 0 {
+      write!(f, "{}!", self.epoch)?;
+    }
+    write!(f, "{}", join_numeric(&self.release))?;
+    if let Some((kind, n)) = &self.pre {
+      write!(f, "{}{}", kind, n)?;
+    }
+    if let Some(post) = self.post {
+      write!(f, ".post{}", post)?;
+    }
+    if let Some(dev) = self.dev {
+      write!(f, ".dev{}", dev)?;
+    }
+    Ok(())
+  }
+}
+
+fn pep440_pre_rank(kind: &str) -> i64 {
+  match kind {
+    "a" => 0,
+    "b" => 1,
+    _ => 2
+  }
+}
+
+fn pep440_channel(channel: &str) -> Result<&'static str> {
+  match channel {
+    "a" | "alpha" => Ok("a"),
+    "b" | "beta" => Ok("b"),
+    "rc" | "c" | "pre" | "preview" => Ok("rc"),
+    other => bail!("Unknown PEP 440 pre-release channel \"{}\".", other)
+  }
+}
+
+impl VersionScheme for Pep440Scheme {
+  fn validate(&self, v: &str) -> Result<()> { Pep440::parse(v).map(|_| ()) }
+
+  fn compare(&self, v1: &str, v2: &str) -> Result<Ordering> {
+    Ok(Pep440::parse(v1)?.key().cmp(&Pep440::parse(v2)?.key()))
+  }
+
+  fn major(&self, v: &str) -> Result<u32> { Ok(Pep440::parse(v)?.release[0] as u32) }
+
+  /// As with SemVer, an increment that fits within a pre-release (or dev release) just drops it. Post-release
+  /// numbers are always dropped.
+  fn apply(&self, size: Size, v: &str) -> Result<String> {
+    let vers = Pep440::parse(v)?;
+    let index = match size {
+      Size::Major => 0,
+      Size::Minor => 1,
+      Size::Patch => 2,
+      Size::None | Size::Empty => return Ok(v.to_string()),
+      Size::Fail => bail!("'fail' size encountered.")
+    };
+
+    let mut release = vers.release.clone();
+    if vers.is_prerelease() && release.iter().skip(index + 1).all(|n| *n == 0) {
+      return Ok(vers.final_of(release).to_string());
+    }
+    bump(&mut release, index);
+    Ok(vers.final_of(release).to_string())
+  }
+
+  fn apply_pre(&self, size: Size, v: &str, channel: &str, existing: &[&str]) -> Result<String> {
+    let channel = pep440_channel(channel)?;
+    if size == Size::None || size == Size::Empty {
+      return Ok(v.to_string());
+    }
+
+    let target = Pep440::parse(&self.apply(size, v)?)?;
+    let last = existing
+      .iter()
+      .filter_map(|v| Pep440::parse(v).ok())
+      .filter(|v| v.epoch == target.epoch && v.trimmed() == target.trimmed())
+      .filter_map(|v| v.pre.filter(|(kind, _)| kind == channel).map(|(_, n)| n))
+      .max();
+
+    let n = last.map(|n| n + 1).unwrap_or(1);
+    Ok(Pep440 { pre: Some((channel.to_string(), n)), ..target }.to_string())
+  }
+
+  fn promote(&self, v: &str) -> Result<String> {
+    let vers = Pep440::parse(v)?;
+    if !vers.is_prerelease() {
+      bail!("Version {} is not a pre-release.", v);
+    }
+    Ok(vers.final_of(vers.release.clone()).to_string())
+  }
+}
+
+/// Maven versions: dotted numbers with an optional `-qualifier`, such as `1.3.0-SNAPSHOT` or `2.1-beta-2`.
+pub struct MavenScheme;
+
+impl MavenScheme {
+  fn parse(v: &str) -> Result<(Vec<u64>, Option<&str>)> {
+    let (nums, qual) = match v.find('-') {
+      Some(i) => (&v[.. i], Some(&v[i + 1 ..])),
+      None => (v, None)
+    };
+    if let Some(qual) = qual {
+      if qual.is_empty() || !qual.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_') {
+        bail!("Illegal qualifier in Maven version {}", v);
+      }
+    }
+    Ok((parse_numeric(nums)?, qual))
+  }
+}
+
+/// Order Maven qualifiers: alpha < beta < milestone < rc < snapshot < (release) < sp < anything else.
+fn maven_qual_key(qual: Option<&str>) -> (u8, u64, String) {
+  let qual = match qual {
+    Some(q) => q.to_ascii_lowercase(),
+    None => return (5, 0, String::new())
+  };
+  let name: String = qual.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+  let num = qual[name.len() ..].trim_start_matches(&['-', '.'][..]).parse().unwrap_or(0);
+  let rank = match name.as_str() {
+    "alpha" | "a" => 0,
+    "beta" | "b" => 1,
+    "milestone" | "m" => 2,
+    "rc" | "cr" => 3,
+    "snapshot" => 4,
+    "" | "ga" | "final" | "release" => 5,
+    "sp" => 6,
+    _ => 7
+  };
+  (rank, num, if rank == 7 { qual } else { String::new() })
+}
+
+/// Whether the qualifier orders before a plain release.
+fn is_maven_pre(qual: Option<&str>) -> bool { maven_qual_key(qual).0 < 5 }
+
+impl VersionScheme for MavenScheme {
+  fn validate(&self, v: &str) -> Result<()> { MavenScheme::parse(v).map(|_| ()) }
+  fn major(&self, v: &str) -> Result<u32> { Ok(MavenScheme::parse(v)?.0[0] as u32) }
+
+  fn compare(&self, v1: &str, v2: &str) -> Result<Ordering> {
+    let (n1, q1) = MavenScheme::parse(v1)?;
+    let (n2, q2) = MavenScheme::parse(v2)?;
+    Ok(trim_zeros(&n1).cmp(trim_zeros(&n2)).then_with(|| maven_qual_key(q1).cmp(&maven_qual_key(q2))))
+  }
+
+  /// A qualifier that comes before a release (such as a `-SNAPSHOT`) is treated as a pre-release of its numbers, in
+  /// the same way as SemVer: an increment that fits within it just drops the qualifier. Any other qualifier (such as
+  /// `-sp1` or `-final`) is dropped by every increment.
+  fn apply(&self, size: Size, v: &str) -> Result<String> {
+    let (mut nums, qual) = MavenScheme::parse(v)?;
+    let index = match size {
+      Size::Major => 0,
+      Size::Minor => 1,
+      Size::Patch => 2,
+      Size::None | Size::Empty => return Ok(v.to_string()),
+      Size::Fail => bail!("'fail' size encountered.")
+    };
+
+    if !is_maven_pre(qual) || nums.iter().skip(index + 1).any(|n| *n != 0) {
+      bump(&mut nums, index);
+    }
+    Ok(join_numeric(&nums))
+  }
+
+  fn promote(&self, v: &str) -> Result<String> {
+    match MavenScheme::parse(v)? {
+      (nums, qual) if is_maven_pre(qual) => Ok(join_numeric(&nums)),
+      _ => bail!("Version {} is not a pre-release.", v)
+    }
+  }
+}
+
+fn parse_numeric(v: &str) -> Result<Vec<u64>> {
+  v.split('.')
+    .map(|p| {
+      if p.is_empty() || !p.chars().all(|c| c.is_ascii_digit()) {
+        bail!("Couldn't split {} into numeric parts", v);
+      }
+      Ok(p.parse()?)
+    })
+    .collect()
+}
+
+fn join_numeric(parts: &[u64]) -> String { parts.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(".") }
+
+fn trim_zeros(parts: &[u64]) -> &[u64] {
+  let len = parts.iter().rposition(|n| *n != 0).map(|i| i + 1).unwrap_or(0);
+  &parts[.. len]
+}
+
+/// Increment the part at `index` (adding parts if needed), and reset all following parts to zero.
+fn bump(parts: &mut Vec<u64>, index: usize) {
+  while parts.len() <= index {
+    parts.push(0);
+  }
+  parts[index] += 1;
+  for part in parts.iter_mut().skip(index + 1) {
+    *part = 0;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::{CalVerScheme, Scheme};
+  use crate::config::Size;
+  use std::cmp::Ordering;
+
+  #[test]
+  fn test_semver_apply() {
+    let s = Scheme::SemVer.imp();
+    assert_eq!("1.3.0", s.apply(Size::Minor, "1.2.5").unwrap());
+    assert_eq!("1.3.0", s.apply(Size::Minor, "1.3.0-rc.1").unwrap());
+    assert_eq!("2.0.0", s.apply(Size::Major, "1.3.0-rc.1").unwrap());
+    assert_eq!("1.3.0", s.apply(Size::Patch, "1.3.0-rc.1+b.7").unwrap());
+    assert_eq!("1.3.0-rc.1", s.apply(Size::None, "1.3.0-rc.1").unwrap());
+  }
+
+  #[test]
+  fn test_semver_apply_pre() {
+    let s = Scheme::SemVer.imp();
+    let tags = ["1.3.0-beta.1", "1.3.0-beta.2"];
+    assert_eq!("1.3.0-beta.1", s.apply_pre(Size::Minor, "1.2.5", "beta", &[]).unwrap());
+    assert_eq!("1.3.0-beta.3", s.apply_pre(Size::Minor, "1.2.5", "beta", &tags).unwrap());
+    assert_eq!("1.3.0-beta.3", s.apply_pre(Size::Patch, "1.3.0-beta.2", "beta", &tags).unwrap());
+    assert_eq!("1.3.0-rc.1", s.apply_pre(Size::Patch, "1.3.0-beta.2", "rc", &tags).unwrap());
+    assert_eq!("1.3.0-beta.2", s.apply_pre(Size::None, "1.3.0-beta.2", "beta", &tags).unwrap());
+  }
+
+  #[test]
+  fn test_calver() {
+    assert_eq!("2020.6.3", CalVerScheme::apply_on(Size::Minor, "2020.6.2", 2020, 6).unwrap());
+    assert_eq!("2020.7.0", CalVerScheme::apply_on(Size::Patch, "2020.6.2", 2020, 7).unwrap());
+    assert_eq!("2021.1.0", CalVerScheme::apply_on(Size::Major, "2020.12.9", 2021, 1).unwrap());
+    assert_eq!("2020.06.2", CalVerScheme::apply_on(Size::None, "2020.06.2", 2021, 1).unwrap());
+    assert_eq!("2020.06.3", CalVerScheme::apply_on(Size::Patch, "2020.06.2", 2020, 6).unwrap());
+    assert_eq!("2020.07.0", CalVerScheme::apply_on(Size::Minor, "2020.06.2", 2020, 7).unwrap());
+    assert_eq!("2020.10.10", CalVerScheme::apply_on(Size::Patch, "2020.10.09", 2020, 10).unwrap());
+    assert!(Scheme::CalVer.imp().validate("1.2.3").is_err());
+    assert!(Scheme::CalVer.imp().less_than("2020.6.2", "2020.10.0").unwrap());
+  }
+
+  #[test]
+  fn test_two_part() {
+    let s = Scheme::TwoPart.imp();
+    assert_eq!("2.0", s.apply(Size::Major, "1.4").unwrap());
+    assert_eq!("1.5", s.apply(Size::Minor, "1.4").unwrap());
+    assert_eq!("1.5", s.apply(Size::Patch, "1.4").unwrap());
+    assert!(s.validate("1.4.0").is_err());
+  }
+
+  #[test]
+  fn test_four_part() {
+    let s = Scheme::FourPart.imp();
+    assert_eq!("2.0.0.0", s.apply(Size::Major, "1.4.2.7").unwrap());
+    assert_eq!("1.5.0.0", s.apply(Size::Minor, "1.4.2.7").unwrap());
+    assert_eq!("1.4.3.0", s.apply(Size::Patch, "1.4.2.7").unwrap());
+    assert!(s.less_than("1.4.2.7", "1.4.2.10").unwrap());
+    assert!(s.validate("1.4.2").is_err());
+  }
+
+  #[test]
+  fn test_pep440() {
+    let s = Scheme::Pep440.imp();
+    assert_eq!("1.3.0", s.apply(Size::Minor, "1.2.3").unwrap());
+    assert_eq!("1.3", s.apply(Size::Minor, "1.2").unwrap());
+    assert_eq!("1.2.4", s.apply(Size::Patch, "1.2.3.post2").unwrap());
+    assert_eq!("1.3.0", s.apply(Size::Minor, "1.3.0rc1").unwrap());
+    assert_eq!("2.0.0", s.apply(Size::Major, "1.3.0rc1").unwrap());
+    assert_eq!("1.3.0rc2", s.apply_pre(Size::Minor, "1.2.3", "rc", &["1.3.0rc1"]).unwrap());
+    assert_eq!("1.3.0b1", s.apply_pre(Size::Minor, "1.2.3", "beta", &["1.3.0rc1"]).unwrap());
+    assert_eq!("1.3.0", s.promote("1.3.0rc2").unwrap());
+    assert!(s.validate("1.3.0-rc.1").is_err());
+
+    let order = ["1.0.dev1", "1.0a1", "1.0a2.dev1", "1.0a2", "1.0b1", "1.0rc1", "1.0", "1.0.post1", "1.1", "1!0.1"];
+    for pair in order.windows(2) {
+      assert_eq!(Ordering::Less, s.compare(pair[0], pair[1]).unwrap(), "{} < {}", pair[0], pair[1]);
+    }
+    assert_eq!(Ordering::Equal, s.compare("1.2", "1.2.0").unwrap());
+  }
+
+  #[test]
+  fn test_maven() {
+    let s = Scheme::Maven.imp();
+    assert_eq!("1.3.0", s.apply(Size::Minor, "1.3.0-SNAPSHOT").unwrap());
+    assert_eq!("1.4.0", s.apply(Size::Minor, "1.3.1-SNAPSHOT").unwrap());
+    assert_eq!("1.3.1", s.apply(Size::Patch, "1.3.0").unwrap());
+    assert_eq!("1.3.0", s.promote("1.3.0-rc-2").unwrap());
+    assert!(s.promote("1.3.0-sp1").is_err());
+
+    assert_eq!("1.3.1", s.apply(Size::Patch, "1.3.0-sp1").unwrap());
+    assert_eq!("1.4.0", s.apply(Size::Minor, "1.3.0-sp1").unwrap());
+    assert_eq!("1.3.1", s.apply(Size::Patch, "1.3.0-final").unwrap());
+    assert_eq!("1.3.1", s.apply(Size::Patch, "1.3.0-custom2").unwrap());
+    assert_eq!("2.0.0", s.apply(Size::Major, "1.3.0-custom2").unwrap());
+
+    let order = ["1.0-alpha-1", "1.0-beta", "1.0-rc1", "1.0-SNAPSHOT", "1.0", "1.0-sp1", "1.0.1"];
+    for pair in order.windows(2) {
+      assert_eq!(Ordering::Less, s.compare(pair[0], pair[1]).unwrap(), "{} < {}", pair[0], pair[1]);
+    }
+  }
+}
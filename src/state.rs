@@ -3,7 +3,8 @@
 use crate::config::{HookSet, ProjectId};
 use crate::errors::{Result, ResultExt as _};
 use crate::git::{FromTagBuf, Repo, Slice};
-use crate::mark::{NamedData, Picker};
+use crate::mark::{Encoding, NamedData, Picker};
+use error_chain::bail;
 use log::{trace, warn};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -14,10 +15,14 @@ use std::path::{Path, PathBuf};
 
 pub trait StateRead: FilesRead {
   fn latest_tag(&self, proj: &ProjectId) -> Option<&String>;
+
+  /// Whether this is the state of the working directory, in which commands can be run to read values.
+  fn is_current(&self) -> bool;
 }
 
 impl<S: StateRead> StateRead for &S {
   fn latest_tag(&self, proj: &ProjectId) -> Option<&String> { <S as StateRead>::latest_tag(*self, proj) }
+  fn is_current(&self) -> bool { <S as StateRead>::is_current(*self) }
 }
 
 pub trait FilesRead {
@@ -47,6 +52,7 @@ impl FilesRead for CurrentState {
 
 impl StateRead for CurrentState {
   fn latest_tag(&self, proj: &ProjectId) -> Option<&String> { self.tags.latest(proj) }
+  fn is_current(&self) -> bool { true }
 }
 
 impl CurrentState {
@@ -60,7 +66,7 @@ pub struct CurrentFiles {
 
 impl FilesRead for CurrentFiles {
   fn has_file(&self, path: &Path) -> Result<bool> { Ok(self.root.join(path).exists()) }
-  fn read_file(&self, path: &Path) -> Result<String> { Ok(std::fs::read_to_string(&self.root.join(path))?) }
+  fn read_file(&self, path: &Path) -> Result<String> { Ok(NamedData::read(&self.root.join(path))?.into()) }
 
   fn subdirs(&self, root: Option<&String>, regex: &str) -> Result<Vec<String>> {
     let filter = Regex::new(regex)?;
@@ -91,6 +97,7 @@ impl<'r> FilesRead for PrevState<'r> {
 
 impl<'r> StateRead for PrevState<'r> {
   fn latest_tag(&self, proj: &ProjectId) -> Option<&String> { self.tags.latest(proj) }
+  fn is_current(&self) -> bool { false }
 }
 
 impl<'r> PrevState<'r> {
@@ -118,23 +125,35 @@ impl<'r> PrevFiles<'r> {
 #[derive(Debug)]
 pub struct OldTags {
   current: HashMap<ProjectId, String>,
-  prev: HashMap<ProjectId, String>
+  prev: HashMap<ProjectId, String>,
+  all: HashMap<ProjectId, Vec<String>>
 }
 
 impl OldTags {
-  pub fn new(current: HashMap<ProjectId, String>, prev: HashMap<ProjectId, String>) -> OldTags {
-    OldTags { current, prev }
+  pub fn new(
+    current: HashMap<ProjectId, String>, prev: HashMap<ProjectId, String>, all: HashMap<ProjectId, Vec<String>>
+  ) -> OldTags {
+    OldTags { current, prev, all }
   }
 
   pub fn latest(&self, proj: &ProjectId) -> Option<&String> { self.current.get(proj) }
   pub fn current(&self) -> &HashMap<ProjectId, String> { &self.current }
 
-  pub fn slice_to_prev(&self) -> Result<OldTags> { Ok(OldTags::new(self.prev.clone(), HashMap::new())) }
+  /// All versions found in the tags of a project, whether or not they are reachable from the current commit.
+  pub fn all_versions(&self, proj: &ProjectId) -> &[String] {
+    self.all.get(proj).map(|v| v.as_slice()).unwrap_or(&[])
+  }
+
+  pub fn slice_to_prev(&self) -> Result<OldTags> {
+    Ok(OldTags::new(self.prev.clone(), HashMap::new(), HashMap::new()))
+  }
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct StateWrite {
   writes: Vec<FileWrite>,
+  #[serde(default)]
+  verifies: Vec<FileVerify>,
   proj_writes: HashSet<ProjectId>,
   tag_head: Vec<String>,
   tag_commit: HashMap<String, String>,
@@ -150,6 +169,7 @@ impl StateWrite {
   pub fn new() -> StateWrite {
     StateWrite {
       writes: Vec::new(),
+      verifies: Vec::new(),
       tag_head: Vec::new(),
       tag_commit: HashMap::new(),
       tag_head_or_last: Vec::new(),
@@ -170,6 +190,28 @@ impl StateWrite {
     Ok(())
   }
 
+  pub fn run_command<C: ToString>(
+    &mut self, cmd: &str, root: Option<&String>, content: C, proj_id: &ProjectId
+  ) -> Result<()> {
+    let (cmd, root) = (cmd.to_string(), root.cloned());
+    self.writes.push(FileWrite::Command { cmd, root, val: content.to_string() });
+    self.proj_writes.insert(proj_id.clone());
+    Ok(())
+  }
+
+  /// Check, once all files are written and hooks run, that a location has the given value.
+  pub fn verify_mark<C: ToString>(&mut self, pick: PickPath, content: C) -> Result<()> {
+    self.verifies.push(FileVerify::Mark { pick, val: content.to_string() });
+    Ok(())
+  }
+
+  /// Check, once all files are written and hooks run, that a read command outputs the given value.
+  pub fn verify_command<C: ToString>(&mut self, cmd: &str, root: Option<&String>, content: C) -> Result<()> {
+    let (cmd, root) = (cmd.to_string(), root.cloned());
+    self.verifies.push(FileVerify::Command { cmd, root, val: content.to_string() });
+    Ok(())
+  }
+
   pub fn tag_head_or_last<T: ToString>(&mut self, vers: &str, tag: T, proj: &ProjectId) -> Result<()> {
     let tag = tag.to_string();
     trace!("head_or_last on {} tagged with {}.", proj, tag);
@@ -191,6 +233,11 @@ impl StateWrite {
       }
     }
 
+    for verify in &self.verifies {
+      verify.verify()?;
+    }
+    self.verifies.clear();
+
     let me = take(self);
     let prev_tag = data.prev_tag.to_string();
     let last_commits = data.last_commits.clone();
@@ -311,7 +358,8 @@ impl PrevTagMessage {
 #[derive(Deserialize, Serialize)]
 enum FileWrite {
   Write { path: PathBuf, val: String },
-  Update { pick: PickPath, val: String }
+  Update { pick: PickPath, val: String },
+  Command { cmd: String, root: Option<String>, val: String }
 }
 
 impl FileWrite {
@@ -324,33 +372,97 @@ impl FileWrite {
       //   let mut file = OpenOptions::new().append(true).open(path)?;
       //   Ok(file.write_all(val.as_bytes())?)
       // }
-      FileWrite::Update { pick, val } => pick.write_value(val)
+      FileWrite::Update { pick, val } => pick.write_value(val),
+      FileWrite::Command { cmd, root, val } => {
+        let mut command = std::process::Command::new("bash");
+        if let Some(root) = root {
+          command.current_dir(root);
+        }
+        let status = command.env(NEW_VERSION_VAR, val).args(["-e", "-c", cmd]).status()?;
+        if !status.success() {
+          bail!("Unable to run write command {}.", cmd);
+        }
+        Ok(())
+      }
     }
   }
 }
 
+#[derive(Deserialize, Serialize)]
+enum FileVerify {
+  Mark { pick: PickPath, val: String },
+  Command { cmd: String, root: Option<String>, val: String }
+}
+
+impl FileVerify {
+  pub fn verify(&self) -> Result<()> {
+    match self {
+      FileVerify::Mark { pick, val } => {
+        if let Some(found) = pick.read_values()?.into_iter().find(|v| v != val) {
+          bail!("Found {} in {}, expected {}.", found, pick.file.to_string_lossy(), val);
+        }
+        Ok(())
+      }
+      FileVerify::Command { cmd, root, val } => {
+        let found = read_command(cmd, root.as_ref())?;
+        if &found != val {
+          bail!("Read command {} output {}, expected {}.", cmd, found, val);
+        }
+        Ok(())
+      }
+    }
+  }
+}
+
+/// Run a `command` location's read command in the root, and return its trimmed output.
+pub fn read_command(cmd: &str, root: Option<&String>) -> Result<String> {
+  let mut command = std::process::Command::new("bash");
+  if let Some(root) = root {
+    command.current_dir(root);
+  }
+  let output = command.args(["-e", "-c", cmd]).output()?;
+  if !output.status.success() {
+    bail!("Unable to run read command {}.", cmd);
+  }
+
+  let value = std::str::from_utf8(&output.stdout)?.trim();
+  if value.is_empty() {
+    bail!("No version from read command {}.", cmd);
+  }
+  Ok(value.to_string())
+}
+
+/// The environment variable that holds the new version for a `command` location's write command.
+const NEW_VERSION_VAR: &str = "VERSIO_NEW_VERSION";
+
 #[derive(Deserialize, Serialize)]
 pub struct PickPath {
   file: PathBuf,
-  picker: Picker
+  picker: Picker,
+  #[serde(default)]
+  all: bool
 }
 
 impl PickPath {
-  pub fn new(file: PathBuf, picker: Picker) -> PickPath { PickPath { file, picker } }
+  pub fn new(file: PathBuf, picker: Picker, all: bool) -> PickPath { PickPath { file, picker, all } }
 
   pub fn write_value(&self, val: &str) -> Result<()> {
-    let data =
-      std::fs::read_to_string(&self.file).chain_err(|| format!("Can't read file {}.", self.file.to_string_lossy()))?;
-    let data = NamedData::new(self.file.clone(), data);
-    let mut mark = self.picker.scan(data)?;
+    let data = NamedData::read(&self.file)?;
+    let mut mark = if self.all { self.picker.scan_all(data)? } else { self.picker.scan(data)? };
     mark.write_new_value(val)?;
     Ok(())
   }
+
+  pub fn read_values(&self) -> Result<Vec<String>> {
+    let data: String = NamedData::read(&self.file)?.into();
+    let marks = if self.all { self.picker.find_all(&data)? } else { vec![self.picker.find(&data)?] };
+    Ok(marks.into_iter().map(|m| m.into_value()).collect())
+  }
 }
 
 pub fn read_from_slice<P: AsRef<Path>>(slice: &Slice, path: P) -> Result<String> {
   let path = path.as_ref().to_string_lossy().to_string();
   let blob = slice.blob(&path)?;
-  let cont: &str = std::str::from_utf8(blob.content()).chain_err(|| format!("Not UTF8: {}", path))?;
-  Ok(cont.to_string())
+  let (cont, _) = Encoding::decode(blob.content()).chain_err(|| format!("Can't decode {}", path))?;
+  Ok(cont)
 }
@@ -0,0 +1,256 @@
+//! Parsing and comparison of SemVer 2.0 version numbers.
+
+use crate::errors::Result;
+use error_chain::bail;
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A full SemVer 2.0 version: `major.minor.patch`, with an optional `-pre.release` and `+build.metadata`.
+///
+/// See https://semver.org/spec/v2.0.0.html for the grammar and precedence rules.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SemVer {
+  major: u32,
+  minor: u32,
+  patch: u32,
+  pre: Vec<PreId>,
+  build: Vec<String>
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PreId {
+  Num(u64),
+  Alpha(String)
+}
+
+impl SemVer {
+  pub fn new(major: u32, minor: u32, patch: u32) -> SemVer {
+    SemVer { major, minor, patch, pre: Vec::new(), build: Vec::new() }
+  }
+
+  pub fn parse(v: &str) -> Result<SemVer> {
+    let (rest, build) = match v.find('+') {
+      Some(i) => (&v[.. i], split_ids(&v[i + 1 ..], v, false)?.into_iter().map(|id| id.to_string()).collect()),
+      None => (v, Vec::new())
+    };
+    let (core, pre) = match rest.find('-') {
+      Some(i) => (&rest[.. i], split_ids(&rest[i + 1 ..], v, true)?),
+      None => (rest, Vec::new())
+    };
+
+    let core: Vec<_> = core.split('.').collect();
+    if core.len() != 3 {
+      bail!("Not a 3-part version: {}", v);
+    }
+    let major = parse_num(core[0], v)?;
+    let minor = parse_num(core[1], v)?;
+    let patch = parse_num(core[2], v)?;
+
+    let pre = pre.into_iter().map(|id| to_pre_id(id, v)).collect::<Result<_>>()?;
+    Ok(SemVer { major, minor, patch, pre, build })
+  }
+
+  pub fn is_prerelease(&self) -> bool { !self.pre.is_empty() }
+  pub fn parts(&self) -> [u32; 3] { [self.major, self.minor, self.patch] }
+
+  /// Strip the pre-release and build metadata, leaving only the `major.minor.patch` core.
+  pub fn core(&self) -> SemVer { SemVer::new(self.major, self.minor, self.patch) }
+
+  /// Create the `{channel}.N` pre-release of this version's core, where `N` is one past the highest such
+  /// pre-release found in `existing`.
+  pub fn next_pre<'a, I: IntoIterator<Item = &'a str>>(&self, channel: &str, existing: I) -> SemVer {
+    let last = existing
+      .into_iter()
+      .filter_map(|v| SemVer::parse(v).ok())
+      .filter(|v| v.parts() == self.parts())
+      .filter_map(|v| match v.pre.as_slice() {
+        [PreId::Alpha(c), PreId::Num(n)] if c == channel => Some(*n),
+        _ => None
+      })
+      .max()
+      .unwrap_or(0);
+
+    let pre = vec![PreId::Alpha(channel.to_string()), PreId::Num(last + 1)];
+    SemVer { major: self.major, minor: self.minor, patch: self.patch, pre, build: Vec::new() }
+  }
+
+  /// Compare two versions by SemVer precedence, which ignores build metadata.
+  pub fn precedence(&self, other: &SemVer) -> Ordering {
+    self
+      .parts()
+      .cmp(&other.parts())
+      .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => cmp_pre(&self.pre, &other.pre)
+      })
+  }
+}
+
+impl fmt::Display for SemVer {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+    if !self.pre.is_empty() {
+      let pre: Vec<_> = self.pre.iter().map(|id| id.to_string()).collect();
+      write!(f, "-{}", pre.join("."))?;
+    }
+    if !self.build.is_empty() {
+      write!(f, "+{}", self.build.join("."))?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Display for PreId {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      PreId::Num(n) => write!(f, "{}", n),
+      PreId::Alpha(s) => write!(f, "{}", s)
+    }
+  }
+}
+
+/// Verify that a pre-release channel (like "beta" or "rc") is a single non-numeric identifier.
+pub fn validate_channel(channel: &str) -> Result<()> {
+  let ids = split_ids(channel, channel, true)?;
+  if ids.len() != 1 || ids[0].chars().all(|c| c.is_ascii_digit()) {
+    bail!("Illegal pre-release channel \"{}\"", channel);
+  }
+  Ok(())
+}
+
+fn cmp_pre(p1: &[PreId], p2: &[PreId]) -> Ordering {
+  for (id1, id2) in p1.iter().zip(p2.iter()) {
+    let ord = match (id1, id2) {
+      (PreId::Num(n1), PreId::Num(n2)) => n1.cmp(n2),
+      (PreId::Num(_), PreId::Alpha(_)) => Ordering::Less,
+      (PreId::Alpha(_), PreId::Num(_)) => Ordering::Greater,
+      (PreId::Alpha(s1), PreId::Alpha(s2)) => s1.cmp(s2)
+    };
+    if ord != Ordering::Equal {
+      return ord;
+    }
+  }
+  p1.len().cmp(&p2.len())
+}
+
+fn split_ids<'a>(ids: &'a str, v: &str, is_pre: bool) -> Result<Vec<&'a str>> {
+  let ids: Vec<_> = ids.split('.').collect();
+  for id in &ids {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+      bail!("Illegal {} identifier \"{}\" in {}", if is_pre { "pre-release" } else { "build" }, id, v);
+    }
+  }
+  Ok(ids)
+}
+
+fn to_pre_id(id: &str, v: &str) -> Result<PreId> {
+  if id.chars().all(|c| c.is_ascii_digit()) {
+    if id.len() > 1 && id.starts_with('0') {
+      bail!("Leading zero in pre-release identifier \"{}\" in {}", id, v);
+    }
+    Ok(PreId::Num(id.parse()?))
+  } else {
+    Ok(PreId::Alpha(id.to_string()))
+  }
+}
+
+fn parse_num(part: &str, v: &str) -> Result<u32> {
+  if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+    bail!("Couldn't split {} into parts", v);
+  }
+  if part.len() > 1 && part.starts_with('0') {
+    bail!("Leading zero in {}", v);
+  }
+  Ok(part.parse()?)
+}
+
+#[cfg(test)]
+mod test {
+  use super::{validate_channel, SemVer};
+  use std::cmp::Ordering;
+
+  #[test]
+  fn test_parse_simple() {
+    let v = SemVer::parse("1.2.3").unwrap();
+    assert_eq!([1, 2, 3], v.parts());
+    assert!(!v.is_prerelease());
+    assert_eq!("1.2.3", v.to_string());
+  }
+
+  #[test]
+  fn test_parse_full() {
+    let v = SemVer::parse("2.0.0-rc.1+build.7").unwrap();
+    assert_eq!([2, 0, 0], v.parts());
+    assert!(v.is_prerelease());
+    assert_eq!("2.0.0-rc.1+build.7", v.to_string());
+  }
+
+  #[test]
+  fn test_parse_hyphens() {
+    let v = SemVer::parse("1.0.0-x-y-z.--+a-b").unwrap();
+    assert_eq!("1.0.0-x-y-z.--+a-b", v.to_string());
+  }
+
+  #[test]
+  fn test_parse_bad() {
+    assert!(SemVer::parse("1.2").is_err());
+    assert!(SemVer::parse("1.2.3.4").is_err());
+    assert!(SemVer::parse("01.2.3").is_err());
+    assert!(SemVer::parse("1.2.3-").is_err());
+    assert!(SemVer::parse("1.2.3-rc..1").is_err());
+    assert!(SemVer::parse("1.2.3-01").is_err());
+    assert!(SemVer::parse("1.2.3+").is_err());
+    assert!(SemVer::parse("1.2.3-rc_1").is_err());
+    assert!(SemVer::parse("v1.2.3").is_err());
+  }
+
+  #[test]
+  fn test_precedence_spec() {
+    let order = [
+      "1.0.0-alpha",
+      "1.0.0-alpha.1",
+      "1.0.0-alpha.beta",
+      "1.0.0-beta",
+      "1.0.0-beta.2",
+      "1.0.0-beta.11",
+      "1.0.0-rc.1",
+      "1.0.0",
+      "1.0.1",
+      "1.1.0",
+      "2.0.0"
+    ];
+
+    for pair in order.windows(2) {
+      let v1 = SemVer::parse(pair[0]).unwrap();
+      let v2 = SemVer::parse(pair[1]).unwrap();
+      assert_eq!(Ordering::Less, v1.precedence(&v2), "{} < {}", pair[0], pair[1]);
+      assert_eq!(Ordering::Greater, v2.precedence(&v1), "{} > {}", pair[1], pair[0]);
+    }
+  }
+
+  #[test]
+  fn test_next_pre() {
+    let existing = ["1.3.0-beta.1", "1.3.0-beta.2", "1.3.0-rc.7", "1.2.0-beta.9", "1.3.0"];
+    let core = SemVer::parse("1.3.0").unwrap();
+    assert_eq!("1.3.0-beta.3", core.next_pre("beta", existing.iter().copied()).to_string());
+    assert_eq!("1.3.0-alpha.1", core.next_pre("alpha", existing.iter().copied()).to_string());
+  }
+
+  #[test]
+  fn test_validate_channel() {
+    assert!(validate_channel("beta").is_ok());
+    assert!(validate_channel("rc-x").is_ok());
+    assert!(validate_channel("").is_err());
+    assert!(validate_channel("12").is_err());
+    assert!(validate_channel("beta.1").is_err());
+  }
+
+  #[test]
+  fn test_precedence_build() {
+    let v1 = SemVer::parse("1.4.0+build.7").unwrap();
+    let v2 = SemVer::parse("1.4.0+build.8").unwrap();
+    assert_eq!(Ordering::Equal, v1.precedence(&v2));
+  }
+}